@@ -0,0 +1,239 @@
+use std::{
+    any::type_name,
+    collections::HashMap,
+    env, fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
+
+// Enables the cache. Either a directory or "1" for the default location.
+const CACHE_VAR: &str = "AOC_CACHE";
+// When set, a pretty-printed JSON copy is written next to each cache entry.
+const JSON_VAR: &str = "AOC_CACHE_JSON";
+
+/// FNV-1a, used instead of `DefaultHasher` because its output is stable
+/// between runs and compiler versions.
+struct Fnv1a(u64);
+
+impl Hasher for Fnv1a {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= *b as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+}
+
+/// Identifies the running build by the size and modification time of its
+/// executable, so a rebuilt parser never reads what an older one stored.
+fn build_id() -> Option<(u64, u128)> {
+    let metadata = fs::metadata(env::current_exe().ok()?).ok()?;
+    let modified = metadata.modified().ok()?;
+    let nanos = modified.duration_since(UNIX_EPOCH).ok()?.as_nanos();
+    Some((metadata.len(), nanos))
+}
+
+fn key<T, I: Hash + ?Sized>(day: &str, input: &I) -> u64 {
+    let mut hasher = Fnv1a(0xcbf29ce484222325);
+    env!("CARGO_PKG_VERSION").hash(&mut hasher);
+    build_id().hash(&mut hasher);
+    type_name::<T>().hash(&mut hasher);
+    day.hash(&mut hasher);
+    input.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn cache_dir() -> Option<PathBuf> {
+    match env::var(CACHE_VAR) {
+        Ok(v) if v == "1" => Some(Path::new(env!("CARGO_MANIFEST_DIR")).join("target/aoc-cache")),
+        Ok(v) if !v.is_empty() => Some(PathBuf::from(v)),
+        _ => None,
+    }
+}
+
+fn load<T: DeserializeOwned>(path: &Path) -> Option<T> {
+    let bytes = fs::read(path).ok()?;
+    bincode::deserialize(&bytes).ok()
+}
+
+fn store<T: Serialize>(path: &Path, value: &T, with_json: bool) {
+    let write = || -> Result<(), String> {
+        fs::create_dir_all(path.parent().unwrap()).map_err(|e| e.to_string())?;
+        let bytes = bincode::serialize(value).map_err(|e| e.to_string())?;
+        fs::write(path, bytes).map_err(|e| e.to_string())
+    };
+    if let Err(e) = write() {
+        eprintln!("warning: could not write {}: {}", path.display(), e);
+        return;
+    }
+
+    let json_path = path.with_extension("json");
+    let write_json = || -> Result<(), String> {
+        let json = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+        fs::write(&json_path, json).map_err(|e| e.to_string())
+    };
+    if with_json {
+        if let Err(e) = write_json() {
+            eprintln!("warning: could not write {}: {}", json_path.display(), e);
+        }
+    }
+}
+
+/// Map stored as a list of `(key, value)` entries, so that maps with keys
+/// JSON objects cannot have, such as tuples, still get a JSON copy.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Entries<K: Eq + Hash, V>(pub HashMap<K, V>);
+
+impl<K: Eq + Hash + Serialize, V: Serialize> Serialize for Entries<K, V> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(&self.0)
+    }
+}
+
+impl<'de, K, V> Deserialize<'de> for Entries<K, V>
+where
+    K: Eq + Hash + Deserialize<'de>,
+    V: Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let entries: Vec<(K, V)> = Vec::deserialize(deserializer)?;
+        Ok(Entries(entries.into_iter().collect()))
+    }
+}
+
+/// Returns the parsed representation of `input`, reading it from the cache
+/// directory when a previous run already parsed the very same input.
+///
+/// The cache is off unless `AOC_CACHE` is set, so by default this is just
+/// `parse(input)`.
+pub fn cached<I, T, F>(day: &str, input: &I, parse: F) -> T
+where
+    I: Hash + ?Sized,
+    T: Serialize + DeserializeOwned,
+    F: FnOnce(&I) -> T,
+{
+    let with_json = env::var_os(JSON_VAR).is_some();
+    cached_in(cache_dir().as_deref(), with_json, day, input, parse)
+}
+
+fn cached_in<I, T, F>(dir: Option<&Path>, with_json: bool, day: &str, input: &I, parse: F) -> T
+where
+    I: Hash + ?Sized,
+    T: Serialize + DeserializeOwned,
+    F: FnOnce(&I) -> T,
+{
+    let dir = match dir {
+        Some(d) => d,
+        None => return parse(input),
+    };
+
+    let path = dir.join(format!("{}-{:016x}.bin", day, key::<T, I>(day, input)));
+    if let Some(parsed) = load(&path) {
+        return parsed;
+    }
+
+    let parsed = parse(input);
+    store(&path, &parsed, with_json);
+    parsed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("aoc-cache-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn lines(s: &str) -> Vec<String> {
+        s.lines().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn test_reuses_parsed_input() {
+        let dir = temp_dir("reuse");
+        let input = lines("1\n2\n3");
+        let parse =
+            |l: &Vec<String>| -> Vec<i32> { l.iter().map(|n| n.parse().unwrap()).collect() };
+
+        let first: Vec<i32> = cached_in(Some(&dir), true, "day01", &input, parse);
+        let second: Vec<i32> = cached_in(Some(&dir), false, "day01", &input, |_| {
+            panic!("parsed twice")
+        });
+        assert_eq!(vec![1, 2, 3], first);
+        assert_eq!(first, second);
+        assert_eq!(
+            1,
+            fs::read_dir(&dir)
+                .unwrap()
+                .filter(|e| e.as_ref().unwrap().path().extension().unwrap() == "json")
+                .count()
+        );
+
+        let other: Vec<i32> = cached_in(Some(&dir), false, "day01", &lines("4"), parse);
+        assert_eq!(vec![4], other);
+
+        // same input and day, but parsed into another type
+        let unsigned: Vec<u32> = cached_in(Some(&dir), false, "day01", &input, |_| vec![7]);
+        assert_eq!(vec![7], unsigned);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_tuple_keys() {
+        let dir = temp_dir("tuples");
+        let input = "2,3";
+        let parse = |s: &str| -> Entries<(i32, i32), bool> {
+            let (x, y) = s.split_once(',').unwrap();
+            Entries(HashMap::from([(
+                (x.parse().unwrap(), y.parse().unwrap()),
+                true,
+            )]))
+        };
+
+        let first = cached_in(Some(&dir), true, "day14", input, parse);
+        let second: Entries<(i32, i32), bool> =
+            cached_in(Some(&dir), true, "day14", input, |_| panic!("parsed twice"));
+        assert_eq!(first, second);
+        assert_eq!(Some(&true), second.0.get(&(2, 3)));
+
+        let json = fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .find(|p| p.extension().unwrap() == "json")
+            .unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(json).unwrap()).unwrap();
+        assert_eq!(serde_json::json!([[[2, 3], true]]), json);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_recovers_from_corrupted_entry() {
+        let dir = temp_dir("corrupted");
+        let input = "abc";
+        let path = dir.join(format!(
+            "day06-{:016x}.bin",
+            key::<String, _>("day06", input)
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&path, [0xff]).unwrap();
+
+        let parsed: String = cached_in(Some(&dir), false, "day06", input, |s| s.to_uppercase());
+        assert_eq!("ABC", parsed);
+        assert_eq!(Some("ABC".to_string()), load::<String>(&path));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,294 @@
+use serde::{Deserialize, Serialize};
+
+/// Closed range of integers, `begin..=end`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct Interval {
+    pub begin: i64,
+    pub end: i64,
+}
+
+impl Interval {
+    pub fn new(begin: i64, end: i64) -> Interval {
+        assert!(begin <= end, "empty interval {}..={}", begin, end);
+        Interval { begin, end }
+    }
+
+    /// Number of integers in the interval.
+    pub fn size(&self) -> u64 {
+        (self.end - self.begin) as u64 + 1
+    }
+
+    pub fn contains_point(&self, x: i64) -> bool {
+        self.begin <= x && x <= self.end
+    }
+
+    pub fn contains(&self, other: &Interval) -> bool {
+        self.begin <= other.begin && other.end <= self.end
+    }
+
+    pub fn overlaps(&self, other: &Interval) -> bool {
+        self.begin <= other.end && other.begin <= self.end
+    }
+
+    // Overlapping or directly next to each other, i.e. mergeable.
+    fn touches(&self, other: &Interval) -> bool {
+        self.begin <= other.end.saturating_add(1) && other.begin <= self.end.saturating_add(1)
+    }
+
+    pub fn intersection(&self, other: &Interval) -> Option<Interval> {
+        if !self.overlaps(other) {
+            return None;
+        }
+        Some(Interval::new(
+            self.begin.max(other.begin),
+            self.end.min(other.end),
+        ))
+    }
+}
+
+/// Set of integers stored as sorted, disjoint and non-adjacent intervals.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct IntervalSet {
+    intervals: Vec<Interval>,
+}
+
+impl IntervalSet {
+    pub fn new() -> IntervalSet {
+        IntervalSet::default()
+    }
+
+    /// The merged intervals, in increasing order.
+    pub fn intervals(&self) -> &[Interval] {
+        &self.intervals
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    /// Number of integers in the set.
+    pub fn covered(&self) -> u64 {
+        self.intervals.iter().map(|i| i.size()).sum()
+    }
+
+    pub fn contains_point(&self, x: i64) -> bool {
+        let i = self.intervals.partition_point(|i| i.end < x);
+        i < self.intervals.len() && self.intervals[i].contains_point(x)
+    }
+
+    /// Smallest interval holding the whole set.
+    pub fn span(&self) -> Option<Interval> {
+        let first = self.intervals.first()?;
+        let last = self.intervals.last()?;
+        Some(Interval::new(first.begin, last.end))
+    }
+
+    pub fn insert(&mut self, interval: Interval) {
+        // everything from `from` to `to` touches the new interval
+        let from = self
+            .intervals
+            .partition_point(|i| i.end.saturating_add(1) < interval.begin);
+        let to = self
+            .intervals
+            .partition_point(|i| i.begin <= interval.end.saturating_add(1));
+
+        let mut merged = interval;
+        for i in &self.intervals[from..to] {
+            merged = Interval::new(merged.begin.min(i.begin), merged.end.max(i.end));
+        }
+        self.intervals.splice(from..to, [merged]);
+    }
+
+    pub fn union(&self, other: &IntervalSet) -> IntervalSet {
+        let mut all: Vec<Interval> = self.intervals.clone();
+        all.extend(other.intervals.iter().copied());
+        all.into_iter().collect()
+    }
+
+    pub fn intersection(&self, other: &IntervalSet) -> IntervalSet {
+        let mut result = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < self.intervals.len() && j < other.intervals.len() {
+            let (a, b) = (&self.intervals[i], &other.intervals[j]);
+            if let Some(common) = a.intersection(b) {
+                result.push(common);
+            }
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        IntervalSet { intervals: result }
+    }
+
+    pub fn difference(&self, other: &IntervalSet) -> IntervalSet {
+        let mut result = Vec::new();
+        let mut j = 0;
+        for interval in &self.intervals {
+            let mut begin = interval.begin;
+            while j < other.intervals.len() && other.intervals[j].end < begin {
+                j += 1;
+            }
+            let mut k = j;
+            while k < other.intervals.len() && other.intervals[k].begin <= interval.end {
+                let cut = &other.intervals[k];
+                if cut.begin > begin {
+                    result.push(Interval::new(begin, cut.begin - 1));
+                }
+                begin = begin.max(cut.end.saturating_add(1));
+                k += 1;
+            }
+            if begin <= interval.end {
+                result.push(Interval::new(begin, interval.end));
+            }
+        }
+        IntervalSet { intervals: result }
+    }
+
+    /// The holes between the intervals of the set.
+    pub fn gaps(&self) -> IntervalSet {
+        let intervals = self
+            .intervals
+            .windows(2)
+            .map(|w| Interval::new(w[0].end + 1, w[1].begin - 1))
+            .collect();
+        IntervalSet { intervals }
+    }
+}
+
+impl From<Interval> for IntervalSet {
+    fn from(interval: Interval) -> IntervalSet {
+        IntervalSet {
+            intervals: vec![interval],
+        }
+    }
+}
+
+impl FromIterator<Interval> for IntervalSet {
+    /// Merges overlapping and adjacent intervals.
+    fn from_iter<T: IntoIterator<Item = Interval>>(iter: T) -> IntervalSet {
+        let mut all: Vec<Interval> = iter.into_iter().collect();
+        all.sort();
+
+        let mut intervals: Vec<Interval> = Vec::new();
+        for interval in all {
+            match intervals.last_mut() {
+                Some(last) if last.touches(&interval) => last.end = last.end.max(interval.end),
+                _ => intervals.push(interval),
+            }
+        }
+        IntervalSet { intervals }
+    }
+}
+
+/// Largest number of intervals sharing a single point.
+pub fn max_concurrency(intervals: &[Interval]) -> usize {
+    // an interval starts at `begin` and stops counting after `end`; at equal
+    // positions the ends come first because of `false < true`
+    let mut events: Vec<(i64, bool)> = Vec::new();
+    for i in intervals {
+        events.push((i.begin, true));
+        events.push((i.end + 1, false));
+    }
+    events.sort();
+
+    let mut current = 0;
+    let mut max = 0;
+    for (_, starts) in events {
+        if starts {
+            current += 1;
+            max = max.max(current);
+        } else {
+            current -= 1;
+        }
+    }
+    max
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(intervals: &[(i64, i64)]) -> IntervalSet {
+        intervals
+            .iter()
+            .map(|(b, e)| Interval::new(*b, *e))
+            .collect()
+    }
+
+    #[test]
+    fn test_interval() {
+        let a = Interval::new(2, 6);
+        assert_eq!(5, a.size());
+        assert!(a.contains(&Interval::new(3, 6)));
+        assert!(!a.contains(&Interval::new(3, 7)));
+        assert!(a.overlaps(&Interval::new(6, 8)));
+        assert!(!a.overlaps(&Interval::new(7, 8)));
+        assert_eq!(
+            Some(Interval::new(4, 6)),
+            a.intersection(&Interval::new(4, 8))
+        );
+        assert_eq!(None, a.intersection(&Interval::new(0, 1)));
+    }
+
+    #[test]
+    fn test_merge() {
+        let s = set(&[(5, 7), (1, 2), (3, 3), (10, 12), (11, 20)]);
+        assert_eq!(
+            &[
+                Interval::new(1, 3),
+                Interval::new(5, 7),
+                Interval::new(10, 20)
+            ],
+            s.intervals()
+        );
+        assert_eq!(3 + 3 + 11, s.covered());
+        assert_eq!(set(&[(4, 4), (8, 9)]), s.gaps());
+        assert_eq!(Some(Interval::new(1, 20)), s.span());
+        assert!(s.contains_point(6));
+        assert!(!s.contains_point(8));
+
+        let mut inserted = s.clone();
+        inserted.insert(Interval::new(4, 8));
+        assert_eq!(set(&[(1, 8), (10, 20)]), inserted);
+        inserted.insert(Interval::new(9, 9));
+        assert_eq!(set(&[(1, 20)]), inserted);
+    }
+
+    #[test]
+    fn test_algebra() {
+        let a = set(&[(0, 5), (10, 15)]);
+        let b = set(&[(3, 11), (14, 20)]);
+        assert_eq!(set(&[(0, 20)]), a.union(&b));
+        assert_eq!(set(&[(3, 5), (10, 11), (14, 15)]), a.intersection(&b));
+        assert_eq!(set(&[(0, 2), (12, 13)]), a.difference(&b));
+        assert_eq!(set(&[(6, 9), (16, 20)]), b.difference(&a));
+        assert_eq!(
+            set(&[(0, 20)]),
+            IntervalSet::from(Interval::new(0, 20)).union(&a)
+        );
+        assert!(a.difference(&a).is_empty());
+    }
+
+    #[test]
+    fn test_max_concurrency() {
+        let intervals = [
+            Interval::new(1, 3),
+            Interval::new(3, 5),
+            Interval::new(4, 4),
+        ];
+        assert_eq!(2, max_concurrency(&intervals));
+        let nested = [
+            Interval::new(1, 10),
+            Interval::new(2, 3),
+            Interval::new(3, 4),
+        ];
+        assert_eq!(3, max_concurrency(&nested));
+        assert_eq!(
+            1,
+            max_concurrency(&[Interval::new(1, 3), Interval::new(4, 5)])
+        );
+        assert_eq!(0, max_concurrency(&[]));
+    }
+}
@@ -0,0 +1,175 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    env,
+    sync::atomic::{AtomicUsize, Ordering::Relaxed},
+};
+
+// When set, every measured step reports its allocations on stderr.
+const REPORT_VAR: &str = "AOC_MEMORY";
+
+/// The system allocator with a few counters on top, installed for every day
+/// so that memory usage can be reported without external tools.
+struct Counting;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+// Like PEAK, but never reset by `measure`.
+static HIGH_WATER: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+impl Counting {
+    fn record_alloc(size: usize) {
+        let current = CURRENT.fetch_add(size, Relaxed) + size;
+        PEAK.fetch_max(current, Relaxed);
+        HIGH_WATER.fetch_max(current, Relaxed);
+        ALLOCATIONS.fetch_add(1, Relaxed);
+        ALLOCATED.fetch_add(size, Relaxed);
+    }
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            Counting::record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc_zeroed(layout) };
+        if !ptr.is_null() {
+            Counting::record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+        CURRENT.fetch_sub(layout.size(), Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = unsafe { System.realloc(ptr, layout, new_size) };
+        if !new_ptr.is_null() {
+            CURRENT.fetch_sub(layout.size(), Relaxed);
+            Counting::record_alloc(new_size);
+        }
+        new_ptr
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stats {
+    /// Highest heap usage above what was live when the measurement started.
+    pub peak: usize,
+    pub allocations: usize,
+    /// Sum of all allocation sizes, freed or not.
+    pub allocated: usize,
+}
+
+/// Runs `f` and returns its result together with the heap activity it caused.
+///
+/// The counters are process-wide, so allocations made by other threads at the
+/// same time are included.
+pub fn measure<R, F: FnOnce() -> R>(f: F) -> (R, Stats) {
+    let baseline = CURRENT.load(Relaxed);
+    PEAK.store(baseline, Relaxed);
+    let allocations = ALLOCATIONS.load(Relaxed);
+    let allocated = ALLOCATED.load(Relaxed);
+
+    let result = f();
+
+    let stats = Stats {
+        peak: PEAK.load(Relaxed).saturating_sub(baseline),
+        allocations: ALLOCATIONS.load(Relaxed) - allocations,
+        allocated: ALLOCATED.load(Relaxed) - allocated,
+    };
+    (result, stats)
+}
+
+fn human(bytes: usize) -> String {
+    let units = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < units.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, units[unit])
+    }
+}
+
+impl Stats {
+    pub fn summary(&self) -> String {
+        format!(
+            "peak {}, {} allocations, {} allocated",
+            human(self.peak),
+            self.allocations,
+            human(self.allocated)
+        )
+    }
+}
+
+/// Runs one step of a day (parsing or a part) and, when `AOC_MEMORY` is set,
+/// prints its allocation stats to stderr so they do not mix with answers.
+pub fn measured<R, F: FnOnce() -> R>(label: &str, f: F) -> R {
+    if env::var_os(REPORT_VAR).is_none() {
+        return f();
+    }
+
+    let (result, stats) = measure(f);
+    eprintln!("memory {}: {}", label, stats.summary());
+    result
+}
+
+/// Prints totals for the whole run when `AOC_MEMORY` is set.
+pub fn report_total() {
+    if env::var_os(REPORT_VAR).is_none() {
+        return;
+    }
+
+    let stats = Stats {
+        peak: HIGH_WATER.load(Relaxed),
+        allocations: ALLOCATIONS.load(Relaxed),
+        allocated: ALLOCATED.load(Relaxed),
+    };
+    eprintln!("memory total: {}", stats.summary());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_measure() {
+        let (sum, stats) = measure(|| {
+            let values = vec![1u64; 1 << 20];
+            values.iter().sum::<u64>()
+        });
+        assert_eq!(1 << 20, sum);
+        assert!(stats.peak >= 8 << 20);
+        assert!(stats.allocations >= 1);
+        assert!(stats.allocated >= 8 << 20);
+    }
+
+    #[test]
+    fn test_summary() {
+        let stats = Stats {
+            peak: 3 << 20,
+            allocations: 7,
+            allocated: 512,
+        };
+        assert_eq!(
+            "peak 3.0 MiB, 7 allocations, 512 B allocated",
+            stats.summary()
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use std::{
+    env,
+    io::{self, BufRead, Write},
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+};
+
+type Handler<'a, T> = Box<dyn Fn(&T, &[&str]) -> Result<String, String> + 'a>;
+
+struct Command<'a, T> {
+    name: &'static str,
+    usage: &'static str,
+    handler: Handler<'a, T>,
+}
+
+/// Small interactive shell over a parsed input. Each day registers the
+/// commands that make sense for its data; `help` and `quit` are built in.
+pub struct Repl<'a, T> {
+    state: &'a T,
+    commands: Vec<Command<'a, T>>,
+}
+
+/// Returns the input file if the day was started as `dayxx repl <input>`.
+///
+/// Stdin is taken by the REPL itself, so the puzzle input has to come from
+/// a file in that mode.
+pub fn requested() -> Option<PathBuf> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.as_slice() {
+        [mode, path] if mode == "repl" => Some(PathBuf::from(path)),
+        _ => None,
+    }
+}
+
+impl<'a, T> Repl<'a, T> {
+    pub fn new(state: &'a T) -> Repl<'a, T> {
+        Repl {
+            state,
+            commands: Vec::new(),
+        }
+    }
+
+    pub fn command<F>(mut self, name: &'static str, usage: &'static str, handler: F) -> Self
+    where
+        F: Fn(&T, &[&str]) -> Result<String, String> + 'a,
+    {
+        self.commands.push(Command {
+            name,
+            usage,
+            handler: Box::new(handler),
+        });
+        self
+    }
+
+    /// Executes a single line and returns what should be printed, or `None`
+    /// when the session should end.
+    pub fn execute(&self, line: &str) -> Option<String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let (name, args) = match words.split_first() {
+            Some((name, args)) => (*name, args),
+            None => return Some(String::new()),
+        };
+
+        match name {
+            "quit" | "exit" => None,
+            "help" => Some(self.help()),
+            _ => Some(match self.commands.iter().find(|c| c.name == name) {
+                Some(c) => self.call(c, args),
+                None => format!("unknown command '{}', try 'help'", name),
+            }),
+        }
+    }
+
+    fn call(&self, command: &Command<'a, T>, args: &[&str]) -> String {
+        // Parsers panic on malformed input; that should not end the session.
+        let result = panic::catch_unwind(AssertUnwindSafe(|| (command.handler)(self.state, args)));
+        match result {
+            Ok(Ok(output)) => output,
+            Ok(Err(e)) => format!("error: {}\nusage: {} {}", e, command.name, command.usage),
+            Err(_) => format!("error: '{}' panicked", command.name),
+        }
+    }
+
+    fn help(&self) -> String {
+        let mut lines: Vec<String> = self
+            .commands
+            .iter()
+            .map(|c| format!("{} {}", c.name, c.usage).trim_end().to_string())
+            .collect();
+        lines.push("help".to_string());
+        lines.push("quit".to_string());
+        lines.join("\n")
+    }
+
+    pub fn run<R: BufRead, W: Write>(&self, input: R, mut output: W) -> io::Result<()> {
+        write!(output, "> ")?;
+        output.flush()?;
+        for line in input.lines() {
+            match self.execute(&line?) {
+                Some(result) if result.is_empty() => (),
+                Some(result) => writeln!(output, "{}", result)?,
+                None => return Ok(()),
+            }
+            write!(output, "> ")?;
+            output.flush()?;
+        }
+        writeln!(output)
+    }
+
+    pub fn run_stdio(&self) {
+        self.run(io::stdin().lock(), io::stdout())
+            .expect("repl i/o failed");
+    }
+}
+
+/// Parses a single REPL argument, naming it in the error message.
+pub fn arg<V: std::str::FromStr>(args: &[&str], i: usize, name: &str) -> Result<V, String> {
+    let raw = args.get(i).ok_or(format!("missing <{}>", name))?;
+    raw.parse()
+        .map_err(|_| format!("bad <{}>: '{}'", name, raw))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repl(values: &Vec<i32>) -> Repl<'_, Vec<i32>> {
+        Repl::new(values)
+            .command("sum", "", |v, _| Ok(v.iter().sum::<i32>().to_string()))
+            .command("at", "<index>", |v, args| {
+                let i: usize = arg(args, 0, "index")?;
+                v.get(i)
+                    .map(|x| x.to_string())
+                    .ok_or(format!("index {} out of range", i))
+            })
+            .command("boom", "", |_, _| panic!("boom"))
+    }
+
+    #[test]
+    fn test_execute() {
+        let values = vec![1, 2, 3];
+        let repl = repl(&values);
+        assert_eq!(Some("6".to_string()), repl.execute("sum"));
+        assert_eq!(Some("2".to_string()), repl.execute("  at 1 "));
+        assert_eq!(
+            Some("error: index 7 out of range\nusage: at <index>".to_string()),
+            repl.execute("at 7")
+        );
+        assert_eq!(
+            Some("error: bad <index>: 'x'\nusage: at <index>".to_string()),
+            repl.execute("at x")
+        );
+        assert_eq!(
+            Some("unknown command 'foo', try 'help'".to_string()),
+            repl.execute("foo")
+        );
+        assert_eq!(
+            Some("error: 'boom' panicked".to_string()),
+            repl.execute("boom")
+        );
+        assert_eq!(None, repl.execute("quit"));
+    }
+
+    #[test]
+    fn test_run() {
+        let values = vec![4, 5];
+        let mut output = Vec::new();
+        repl(&values)
+            .run("help\n\nsum\nquit\nsum\n".as_bytes(), &mut output)
+            .unwrap();
+        assert_eq!(
+            "> sum\nat <index>\nboom\nhelp\nquit\n> > 9\n> ",
+            String::from_utf8(output).unwrap()
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+// When set, mismatching or missing golden files are rewritten instead of failing.
+const UPDATE_VAR: &str = "UPDATE_SNAPSHOTS";
+
+fn golden_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("snapshots")
+        .join(format!("{}.txt", name))
+}
+
+/// Line diff of two texts, `-` for lines only in `expected` and `+` for lines
+/// only in `actual`. Good enough for the small pictures the days render.
+fn diff(expected: &str, actual: &str) -> String {
+    let old: Vec<&str> = expected.lines().collect();
+    let new: Vec<&str> = actual.lines().collect();
+
+    // lcs[i][j] = longest common subsequence of old[i..] and new[j..]
+    let mut lcs = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut result = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            result.push(format!(" {}", old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            result.push(format!("-{}", old[i]));
+            i += 1;
+        } else {
+            result.push(format!("+{}", new[j]));
+            j += 1;
+        }
+    }
+    result.join("\n")
+}
+
+fn check(path: &Path, actual: &str, update: bool) -> Result<(), String> {
+    let expected = fs::read_to_string(path).ok();
+    if expected.as_deref() == Some(actual) {
+        return Ok(());
+    }
+
+    if update {
+        fs::create_dir_all(path.parent().unwrap()).map_err(|e| e.to_string())?;
+        return fs::write(path, actual).map_err(|e| e.to_string());
+    }
+
+    match expected {
+        None => Err(format!(
+            "missing snapshot {}, run with {}=1 to create it",
+            path.display(),
+            UPDATE_VAR
+        )),
+        Some(expected) => Err(format!(
+            "snapshot {} does not match (-expected +actual), run with {}=1 to accept:\n{}",
+            path.display(),
+            UPDATE_VAR,
+            diff(&expected, actual)
+        )),
+    }
+}
+
+/// Compares `actual` with the golden file `snapshots/<name>.txt`.
+///
+/// With `UPDATE_SNAPSHOTS=1` the golden file is written instead, so changed
+/// renderings can be reviewed through the usual diff of the checked-in file.
+pub fn assert_snapshot(name: &str, actual: &str) {
+    let update = env::var_os(UPDATE_VAR).is_some();
+    if let Err(e) = check(&golden_path(name), actual, update) {
+        panic!("{}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff() {
+        assert_eq!(" a\n-b\n+x\n c\n+d", diff("a\nb\nc", "a\nx\nc\nd"));
+        assert_eq!(" a\n b", diff("a\nb", "a\nb"));
+    }
+
+    #[test]
+    fn test_check() {
+        let dir = env::temp_dir().join(format!("aoc-snapshot-{}", std::process::id()));
+        let path = dir.join("picture.txt");
+
+        assert!(check(&path, "#.\n.#\n", false)
+            .unwrap_err()
+            .starts_with("missing snapshot"));
+        assert_eq!(Ok(()), check(&path, "#.\n.#\n", true));
+        assert_eq!(Ok(()), check(&path, "#.\n.#\n", false));
+
+        let error = check(&path, "#.\n##\n", false).unwrap_err();
+        assert!(error.ends_with(":\n #.\n-.#\n+##"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,50 +1,270 @@
-use std::{cmp::Reverse, collections::BinaryHeap};
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    env, fmt,
+    io::{self, BufRead},
+    process,
+};
 
-use aoc_2022_rust::aoc::input::lines;
+use aoc_2022_rust::aoc::{
+    cache::cached,
+    input::{file_lines, lines},
+    memory::{measured, report_total},
+    repl::{self, Repl},
+};
+use serde::{Deserialize, Serialize};
 
-fn parse(input: Vec<String>) -> Vec<Vec<i32>> {
-    let mut result = Vec::new();
+/// Calories carried by each elf, in input order. Elf indices are 0-based.
+#[derive(Deserialize, Serialize)]
+struct Inventory {
+    elves: Vec<Vec<i32>>,
+    totals: Vec<i32>,
+}
+
+#[derive(Debug, PartialEq)]
+struct Ranked {
+    elf: usize,
+    calories: i32,
+    // how much more than the best elf outside of the ranking
+    lead: i32,
+}
+
+impl Inventory {
+    fn new() -> Inventory {
+        return Inventory {
+            elves: Vec::new(),
+            totals: Vec::new(),
+        };
+    }
+
+    fn push(&mut self, items: Vec<i32>) {
+        self.totals.push(items.iter().sum());
+        self.elves.push(items);
+    }
+
+    fn item_count(&self, elf: usize) -> usize {
+        return self.elves[elf].len();
+    }
+
+    /// The `n` elves carrying the most, best first. Ties go to the lower index.
+    fn top(&self, n: usize) -> Vec<(usize, i32)> {
+        let mut heap = BinaryHeap::<Reverse<(i32, Reverse<usize>)>>::new();
+
+        for (elf, total) in self.totals.iter().enumerate() {
+            heap.push(Reverse((*total, Reverse(elf))));
+            if heap.len() > n {
+                heap.pop();
+            }
+        }
+
+        return heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((total, Reverse(elf)))| (elf, total))
+            .collect();
+    }
+
+    fn top_sum(&self, n: usize) -> i32 {
+        return self.top(n).iter().map(|(_, total)| total).sum();
+    }
+
+    fn top_with_lead(&self, n: usize) -> Vec<Ranked> {
+        let mut top = self.top(n + 1);
+        let threshold = if top.len() > n {
+            top.pop().unwrap().1
+        } else {
+            0
+        };
+
+        return top
+            .into_iter()
+            .map(|(elf, calories)| Ranked {
+                elf,
+                calories,
+                lead: calories - threshold,
+            })
+            .collect();
+    }
+
+    /// Nearest-rank percentile of the per-elf totals, `p` in 0..=100.
+    fn percentile(&self, p: f64) -> Option<i32> {
+        if self.totals.is_empty() {
+            return None;
+        }
+
+        let mut sorted = self.totals.clone();
+        sorted.sort();
+        let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+        return Some(sorted[rank.clamp(1, sorted.len()) - 1]);
+    }
+}
+
+fn parse(input: Vec<String>) -> Inventory {
+    let mut inventory = Inventory::new();
     let mut candidate: Vec<i32> = Vec::new();
     for line in input {
         let line = line.trim();
         if line.is_empty() {
-            result.push(candidate);
+            inventory.push(candidate);
             candidate = Vec::new();
         } else {
             candidate.push(line.parse().unwrap())
         }
     }
 
-    result.push(candidate);
+    inventory.push(candidate);
 
-    return result;
+    return inventory;
 }
 
-fn max_n_elves(input: &Vec<Vec<i32>>, n: usize) -> i32 {
-    let mut heap = BinaryHeap::<Reverse<i32>>::new();
+#[derive(Debug, PartialEq)]
+enum StreamError {
+    Io(String),
+    // 1-based line number and its content
+    BadLine(usize, String),
+    // index of the elf whose total does not fit
+    Overflow(usize),
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StreamError::Io(e) => write!(f, "cannot read input: {}", e),
+            StreamError::BadLine(line, content) => {
+                write!(f, "line {}: '{}' is not a calorie count", line, content)
+            }
+            StreamError::Overflow(elf) => write!(f, "elf {} carries too many calories", elf),
+        }
+    }
+}
 
-    for elf in input {
-        heap.push(Reverse(elf.iter().sum()));
+/// Keeps the `n` best totals while reading elves straight from `reader`, so
+/// memory does not grow with the input. Ties go to the lower index.
+fn stream_top_n<R: BufRead>(mut reader: R, n: usize) -> Result<Vec<(usize, i64)>, StreamError> {
+    let mut heap = BinaryHeap::<Reverse<(i64, Reverse<usize>)>>::new();
+    let mut push = |elf: usize, total: i64| {
+        heap.push(Reverse((total, Reverse(elf))));
         if heap.len() > n {
             heap.pop();
         }
+    };
+
+    let mut line = String::new();
+    let mut line_number = 0;
+    let mut elf = 0;
+    let mut total: i64 = 0;
+    loop {
+        line.clear();
+        let read = reader
+            .read_line(&mut line)
+            .map_err(|e| StreamError::Io(e.to_string()))?;
+        if read == 0 {
+            break;
+        }
+        line_number += 1;
+
+        let item = line.trim();
+        if item.is_empty() {
+            push(elf, total);
+            elf += 1;
+            total = 0;
+            continue;
+        }
+
+        let calories: i64 = item
+            .parse()
+            .map_err(|_| StreamError::BadLine(line_number, item.to_string()))?;
+        total = total
+            .checked_add(calories)
+            .ok_or(StreamError::Overflow(elf))?;
     }
+    push(elf, total);
 
-    return heap.iter().take(n).map(|Reverse(v)| v).sum();
+    return Ok(heap
+        .into_sorted_vec()
+        .into_iter()
+        .map(|Reverse((total, Reverse(elf)))| (elf, total))
+        .collect());
 }
 
-fn first(input: &Vec<Vec<i32>>) -> i32 {
-    return max_n_elves(input, 1);
+fn stream_first_and_second<R: BufRead>(reader: R) -> Result<(i64, i64), StreamError> {
+    let top = stream_top_n(reader, 3)?;
+    let mut second: i64 = 0;
+    for (elf, total) in &top {
+        second = second
+            .checked_add(*total)
+            .ok_or(StreamError::Overflow(*elf))?;
+    }
+    return Ok((top.first().map_or(0, |(_, t)| *t), second));
 }
 
-fn second(input: &Vec<Vec<i32>>) -> i32 {
-    return max_n_elves(input, 3);
+fn first(inventory: &Inventory) -> i32 {
+    return inventory.top_sum(1);
+}
+
+fn second(inventory: &Inventory) -> i32 {
+    return inventory.top_sum(3);
+}
+
+fn repl(inventory: &Inventory) -> Repl<'_, Inventory> {
+    return Repl::new(inventory)
+        .command("first", "", |inventory, _| Ok(first(inventory).to_string()))
+        .command("second", "", |inventory, _| {
+            Ok(second(inventory).to_string())
+        })
+        .command("top", "<n>", |inventory, args| {
+            let n: usize = repl::arg(args, 0, "n")?;
+            Ok(inventory
+                .top_with_lead(n)
+                .iter()
+                .map(|r| format!("elf {}: {} (+{})", r.elf, r.calories, r.lead))
+                .collect::<Vec<String>>()
+                .join("\n"))
+        })
+        .command("percentile", "<p>", |inventory, args| {
+            let p: f64 = repl::arg(args, 0, "p")?;
+            if !(0.0..=100.0).contains(&p) {
+                return Err(format!("{} is not within 0..=100", p));
+            }
+            inventory
+                .percentile(p)
+                .map(|v| v.to_string())
+                .ok_or("no elves".to_string())
+        })
+        .command("items", "<elf>", |inventory, args| {
+            let elf: usize = repl::arg(args, 0, "elf")?;
+            if elf >= inventory.elves.len() {
+                return Err(format!("no elf {}", elf));
+            }
+            Ok(inventory.item_count(elf).to_string())
+        });
 }
 
 fn main() {
-    let parsed = parse(lines());
-    println!("first = {}", first(&parsed));
-    println!("second = {}", second(&parsed));
+    if let Some(path) = repl::requested() {
+        let parsed = cached("day01", &file_lines(&path), |l| parse(l.clone()));
+        return repl(&parsed).run_stdio();
+    }
+
+    // Inputs too large to keep in memory: `day01 stream < input`
+    if env::args().nth(1).as_deref() == Some("stream") {
+        match measured("stream", || stream_first_and_second(io::stdin().lock())) {
+            Ok((first, second)) => {
+                println!("first = {}", first);
+                println!("second = {}", second);
+            }
+            Err(e) => {
+                eprintln!("error: {}", e);
+                process::exit(1);
+            }
+        }
+        return report_total();
+    }
+
+    let parsed = measured("parse", || cached("day01", &lines(), |l| parse(l.clone())));
+    println!("first = {}", measured("first", || first(&parsed)));
+    println!("second = {}", measured("second", || second(&parsed)));
+    report_total();
 }
 
 #[cfg(test)]
@@ -65,7 +285,7 @@ mod tests {
     
     10000";
 
-    fn test_input() -> Vec<Vec<i32>> {
+    fn test_input() -> Inventory {
         return parse(INPUT.split('\n').map(|l| l.to_string()).collect());
     }
 
@@ -78,4 +298,85 @@ mod tests {
     fn test_second() {
         assert_eq!(45000, second(&test_input()));
     }
+
+    #[test]
+    fn test_ranking() {
+        let inventory = test_input();
+        assert_eq!(vec![(3, 24000), (2, 11000), (4, 10000)], inventory.top(3));
+        assert_eq!(
+            vec![
+                Ranked {
+                    elf: 3,
+                    calories: 24000,
+                    lead: 14000
+                },
+                Ranked {
+                    elf: 2,
+                    calories: 11000,
+                    lead: 1000
+                },
+            ],
+            inventory.top_with_lead(2)
+        );
+        assert_eq!(3, inventory.item_count(0));
+        assert_eq!(Some(4000), inventory.percentile(0.0));
+        assert_eq!(Some(10000), inventory.percentile(50.0));
+        assert_eq!(Some(24000), inventory.percentile(100.0));
+    }
+
+    #[test]
+    fn test_push() {
+        let mut inventory = test_input();
+        inventory.push(vec![20000, 5000]);
+        assert_eq!(vec![(5, 25000), (3, 24000)], inventory.top(2));
+        assert_eq!(60000, second(&inventory));
+        assert_eq!(6, inventory.top_with_lead(10).len());
+        assert_eq!(None, Inventory::new().percentile(50.0));
+    }
+
+    #[test]
+    fn test_stream() {
+        let input = INPUT.replace("    ", "");
+        assert_eq!(
+            Ok((24000, 45000)),
+            stream_first_and_second(input.as_bytes())
+        );
+        assert_eq!(
+            Ok(vec![(3, 24000), (2, 11000), (4, 10000)]),
+            stream_top_n(input.as_bytes(), 3)
+        );
+        assert_eq!(Ok((0, 0)), stream_first_and_second("".as_bytes()));
+
+        let big = format!("1\n\n{}\n{}\n", i64::MAX / 2, i64::MAX / 2);
+        assert_eq!(
+            Ok((i64::MAX - 1, i64::MAX)),
+            stream_first_and_second(big.as_bytes())
+        );
+
+        let overflow = format!("{}\n1\n", i64::MAX);
+        assert_eq!(
+            Err(StreamError::Overflow(0)),
+            stream_top_n(overflow.as_bytes(), 1)
+        );
+        assert_eq!(
+            Err(StreamError::BadLine(3, "12x".to_string())),
+            stream_top_n("1\n\n12x\n".as_bytes(), 1)
+        );
+        assert_eq!(
+            "line 3: '12x' is not a calorie count",
+            StreamError::BadLine(3, "12x".to_string()).to_string()
+        );
+    }
+
+    #[test]
+    fn test_repl() {
+        let inventory = test_input();
+        let repl = repl(&inventory);
+        assert_eq!(
+            Some("elf 3: 24000 (+14000)\nelf 2: 11000 (+1000)".to_string()),
+            repl.execute("top 2")
+        );
+        assert_eq!(Some("10000".to_string()), repl.execute("percentile 50"));
+        assert_eq!(Some("1".to_string()), repl.execute("items 1"));
+    }
 }
@@ -1,98 +1,473 @@
-use aoc_2022_rust::aoc::input::lines;
+use std::env;
 
-#[derive(Clone)]
-enum Choice {
-    Rock = 0,
-    Paper = 1,
-    Scissors = 2,
-}
+use aoc_2022_rust::aoc::{
+    cache::cached,
+    input::lines,
+    memory::{measured, report_total},
+};
+use serde::{Deserialize, Serialize};
+
+/// Index into `Rules::names`.
+type Move = usize;
 
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
 enum Outcome {
-    Win,
-    Draw,
-    Loose,
+    Loose = 0,
+    Draw = 1,
+    Win = 2,
+}
+
+/// A rock-paper-scissors-like game: its moves, who beats whom and how
+/// rounds are scored. Every pair of different moves must have a winner.
+#[derive(Clone, Debug)]
+struct Rules {
+    names: Vec<String>,
+    // beats[a][b] is true when a wins against b
+    beats: Vec<Vec<bool>>,
+    move_scores: Vec<u32>,
+    // indexed by Outcome
+    outcome_scores: [u32; 3],
+    // letters used for each move in the opponent's and your column
+    opponent_symbols: Vec<char>,
+    your_symbols: Vec<char>,
 }
 
-type Round = (Choice, Choice, Outcome);
+impl Rules {
+    /// Builds a game from its moves and `(winner, loser)` pairs, scored like
+    /// the puzzle: move i is worth i + 1, and a round 0, 3 or 6 on top.
+    fn new(names: &[&str], wins: &[(&str, &str)]) -> Result<Rules, String> {
+        let n = names.len();
+        if n < 3 {
+            return Err(format!("{} moves cannot both win and lose", n));
+        }
+        if n > 13 {
+            return Err(format!("{} moves do not fit the guide letters", n));
+        }
+
+        let index = |name: &str| -> Result<Move, String> {
+            names
+                .iter()
+                .position(|m| *m == name)
+                .ok_or(format!("unknown move {}", name))
+        };
+
+        let mut beats = vec![vec![false; n]; n];
+        for (winner, loser) in wins {
+            beats[index(winner)?][index(loser)?] = true;
+        }
+
+        for a in 0..n {
+            if beats[a][a] {
+                return Err(format!("{} beats itself", names[a]));
+            }
+            for b in (a + 1)..n {
+                if beats[a][b] == beats[b][a] {
+                    return Err(format!("no single winner for {} vs {}", names[a], names[b]));
+                }
+            }
+            if !beats[a].contains(&true) || !(0..n).any(|b| beats[b][a]) {
+                return Err(format!(
+                    "{} must both win and lose against something",
+                    names[a]
+                ));
+            }
+        }
+
+        return Ok(Rules {
+            names: names.iter().map(|m| m.to_string()).collect(),
+            beats,
+            move_scores: (1..=n as u32).collect(),
+            outcome_scores: [0, 3, 6],
+            opponent_symbols: ('A'..='Z').take(n).collect(),
+            your_symbols: ('A'..='Z').skip(26 - n).collect(),
+        });
+    }
+
+    /// Odd-sized game where every move beats the (n - 1) / 2 moves before it.
+    fn cyclic(names: &[&str]) -> Result<Rules, String> {
+        let n = names.len();
+        if n.is_multiple_of(2) {
+            return Err(format!(
+                "a cyclic game needs an odd number of moves, got {}",
+                n
+            ));
+        }
+
+        let mut wins = Vec::new();
+        for i in 0..n {
+            for d in 1..=(n - 1) / 2 {
+                wins.push((names[i], names[(i + n - d) % n]));
+            }
+        }
+        return Rules::new(names, &wins);
+    }
+
+    fn classic() -> Rules {
+        return Rules::cyclic(&["rock", "paper", "scissors"]).unwrap();
+    }
+
+    fn rpsls() -> Rules {
+        return Rules::new(
+            &["rock", "paper", "scissors", "lizard", "spock"],
+            &[
+                ("scissors", "paper"),
+                ("paper", "rock"),
+                ("rock", "lizard"),
+                ("lizard", "spock"),
+                ("spock", "scissors"),
+                ("scissors", "lizard"),
+                ("lizard", "paper"),
+                ("paper", "spock"),
+                ("spock", "rock"),
+                ("rock", "scissors"),
+            ],
+        )
+        .unwrap();
+    }
+
+    fn outcome(&self, opponent: Move, you: Move) -> Outcome {
+        if self.beats[you][opponent] {
+            return Outcome::Win;
+        }
+        if self.beats[opponent][you] {
+            return Outcome::Loose;
+        }
+        return Outcome::Draw;
+    }
+
+    /// The best scoring move that ends the round with `outcome`.
+    fn response(&self, opponent: Move, outcome: Outcome) -> Move {
+        return (0..self.names.len())
+            .filter(|you| self.outcome(opponent, *you) == outcome)
+            .max_by_key(|you| (self.move_scores[*you], std::cmp::Reverse(*you)))
+            .unwrap();
+    }
+}
+
+type Round = (char, char);
 
 fn parse_round(line: &String) -> Round {
-    let to_choice = |c: char| match c {
-        'A' | 'X' => Choice::Rock,
-        'B' | 'Y' => Choice::Paper,
-        'C' | 'Z' => Choice::Scissors,
-        _ => panic!("Unhandled choice: {}!", c),
-    };
-    let to_outcome = |c: char| match c {
-        'X' => Outcome::Loose,
-        'Y' => Outcome::Draw,
-        'Z' => Outcome::Win,
-        _ => panic!("Unhandled choice: {}!", c),
-    };
-
-    return (
-        to_choice(line.chars().nth(0).unwrap()),
-        to_choice(line.chars().nth(2).unwrap()),
-        to_outcome(line.chars().nth(2).unwrap()),
-    );
+    return (line.chars().nth(0).unwrap(), line.chars().nth(2).unwrap());
 }
 
 fn parse(input: Vec<String>) -> Vec<Round> {
     input.iter().map(parse_round).collect()
 }
 
-fn to_ordinal(choice: &Choice) -> u32 {
-    choice.clone() as u32
+fn kind_score(rules: &Rules, you: Move) -> u32 {
+    return rules.move_scores[you];
+}
+
+fn outcome_score(rules: &Rules, outcome: Outcome) -> u32 {
+    return rules.outcome_scores[outcome as usize];
+}
+
+fn score(rules: &Rules, (opponent, you): (Move, Move)) -> u32 {
+    kind_score(rules, you) + outcome_score(rules, rules.outcome(opponent, you))
 }
 
-fn from_ordinal(ord: u32) -> Choice {
-    match ord % 3 {
-        0 => Choice::Rock,
-        1 => Choice::Paper,
-        2 => Choice::Scissors,
-        _ => panic! {"unreachable"},
+fn opponent_move(rules: &Rules, c: char) -> Result<Move, String> {
+    return rules
+        .opponent_symbols
+        .iter()
+        .position(|s| *s == c)
+        .ok_or(format!("unknown opponent move {}", c));
+}
+
+// Position of `c` in your column's letters, whatever they stand for.
+fn your_symbol(rules: &Rules, c: char) -> Result<usize, String> {
+    return rules
+        .your_symbols
+        .iter()
+        .position(|s| *s == c)
+        .ok_or(format!("unknown move {}", c));
+}
+
+fn to_outcome(c: char) -> Result<Outcome, String> {
+    match c {
+        'X' => Ok(Outcome::Loose),
+        'Y' => Ok(Outcome::Draw),
+        'Z' => Ok(Outcome::Win),
+        _ => Err(format!("unknown outcome {}", c)),
     }
 }
 
-fn kind_score(choice: &Choice) -> u32 {
-    return to_ordinal(choice) + 1;
+/// Total score when the second column says which move to play.
+fn play_as_moves(rules: &Rules, rounds: &Vec<Round>) -> Result<u32, String> {
+    let mut total = 0;
+    for (opponent, you) in rounds {
+        total += score(
+            rules,
+            (opponent_move(rules, *opponent)?, your_symbol(rules, *you)?),
+        );
+    }
+    return Ok(total);
 }
 
-fn outcome_score(opponent: &Choice, you: &Choice) -> u32 {
-    let diff = (3 + to_ordinal(you) - to_ordinal(opponent)) % 3;
-    return ((diff + 1) % 3) * 3;
+/// Total score when the second column says how the round has to end.
+fn play_as_outcomes(rules: &Rules, rounds: &Vec<Round>) -> Result<u32, String> {
+    let mut total = 0;
+    for (opponent, outcome) in rounds {
+        let opponent = opponent_move(rules, *opponent)?;
+        let you = rules.response(opponent, to_outcome(*outcome)?);
+        total += score(rules, (opponent, you));
+    }
+    return Ok(total);
 }
 
-fn score((opponent, you): (&Choice, &Choice)) -> u32 {
-    kind_score(you) + outcome_score(opponent, you)
+fn first(rules: &Rules, rounds: &Vec<Round>) -> u32 {
+    match play_as_moves(rules, rounds) {
+        Ok(v) => v,
+        Err(e) => panic!("Bad guide: {}", e),
+    }
 }
 
-fn first<'a>(rounds: &'a Vec<Round>) -> u32 {
-    let make_choice = |(opponent, you, _): &'a Round| (opponent, you);
+fn second(rules: &Rules, rounds: &Vec<Round>) -> u32 {
+    match play_as_outcomes(rules, rounds) {
+        Ok(v) => v,
+        Err(e) => panic!("Bad guide: {}", e),
+    }
+}
 
-    return rounds.iter().map(make_choice).map(score).sum();
+/// One way of reading the second column of a guide.
+#[derive(Clone, Debug, PartialEq)]
+enum Mapping {
+    // the i-th letter means playing the given move
+    Moves(Vec<Move>),
+    // the i-th letter means ending the round with the given outcome
+    Outcomes(Vec<Outcome>),
 }
 
-fn second<'a>(rounds: &'a Vec<Round>) -> u32 {
-    let make_choice = |(opponent, _, outcome): &'a Round| -> (&'a Choice, Choice) {
-        let you = match outcome {
-            Outcome::Draw => opponent.clone(),
-            Outcome::Loose => from_ordinal((to_ordinal(opponent) + 2) % 3),
-            Outcome::Win => from_ordinal(to_ordinal(opponent) + 4),
+impl Mapping {
+    fn describe(&self, rules: &Rules) -> String {
+        let meanings: Vec<String> = match self {
+            Mapping::Moves(moves) => moves.iter().map(|m| rules.names[*m].clone()).collect(),
+            Mapping::Outcomes(outcomes) => outcomes.iter().map(|o| format!("{:?}", o)).collect(),
         };
-        return (opponent, you);
-    };
+        return rules
+            .your_symbols
+            .iter()
+            .zip(meanings)
+            .map(|(s, m)| format!("{}={}", s, m))
+            .collect::<Vec<String>>()
+            .join(" ");
+    }
+}
 
-    return rounds
-        .iter()
-        .map(make_choice)
-        .map(|round| score((&round.0, &round.1)))
-        .sum();
+fn permutations<T: Clone>(items: &[T]) -> Vec<Vec<T>> {
+    if items.is_empty() {
+        return vec![vec![]];
+    }
+
+    let mut result = Vec::new();
+    for i in 0..items.len() {
+        let mut rest = items.to_vec();
+        let item = rest.remove(i);
+        for mut tail in permutations(&rest) {
+            tail.insert(0, item.clone());
+            result.push(tail);
+        }
+    }
+    return result;
+}
+
+/// Every reading of the second column: each assignment of letters to moves
+/// and, when there are exactly three letters, each assignment to outcomes.
+/// The puzzle's two readings come first.
+fn mappings(rules: &Rules) -> Vec<Mapping> {
+    let moves: Vec<Move> = (0..rules.names.len()).collect();
+    let mut result: Vec<Mapping> = permutations(&moves)
+        .into_iter()
+        .map(Mapping::Moves)
+        .collect();
+    if rules.your_symbols.len() == 3 {
+        let outcomes = [Outcome::Loose, Outcome::Draw, Outcome::Win];
+        result.insert(1, Mapping::Outcomes(outcomes.to_vec()));
+        result.extend(
+            permutations(&outcomes)
+                .into_iter()
+                .skip(1)
+                .map(Mapping::Outcomes),
+        );
+    }
+    return result;
+}
+
+fn score_with(rules: &Rules, mapping: &Mapping, rounds: &Vec<Round>) -> Result<u32, String> {
+    let mut total = 0;
+    for (opponent, you) in rounds {
+        let opponent = opponent_move(rules, *opponent)?;
+        let you = your_symbol(rules, *you)?;
+        let you = match mapping {
+            Mapping::Moves(moves) => moves[you],
+            Mapping::Outcomes(outcomes) => rules.response(opponent, outcomes[you]),
+        };
+        total += score(rules, (opponent, you));
+    }
+    return Ok(total);
+}
+
+/// Highest total possible against the opponent's column, ignoring the guide.
+fn best_score(rules: &Rules, rounds: &Vec<Round>) -> Result<u32, String> {
+    let mut total = 0;
+    for (opponent, _) in rounds {
+        let opponent = opponent_move(rules, *opponent)?;
+        total += (0..rules.names.len())
+            .map(|you| kind_score(rules, you) + outcome_score(rules, rules.outcome(opponent, you)))
+            .max()
+            .unwrap();
+    }
+    return Ok(total);
+}
+
+/// Largest move count whose mappings are all listed; 7 moves already have
+/// 5040 of them.
+const MAX_LISTED: usize = 7;
+
+/// Score of the guide under every mapping, best first.
+fn distribution(rules: &Rules, rounds: &Vec<Round>) -> Result<Vec<(Mapping, u32)>, String> {
+    if rules.names.len() > MAX_LISTED {
+        return Err(format!(
+            "{} moves have too many mappings to list",
+            rules.names.len()
+        ));
+    }
+
+    let mut result = Vec::new();
+    for mapping in mappings(rules) {
+        let total = score_with(rules, &mapping, rounds)?;
+        result.push((mapping, total));
+    }
+    // stable, so equal scores keep the puzzle's readings first
+    result.sort_by_key(|(_, total)| std::cmp::Reverse(*total));
+    return Ok(result);
+}
+
+/// `table[letter][move]` is what the rounds with that letter in the second
+/// column score when it means that move.
+fn letter_scores(rules: &Rules, rounds: &Vec<Round>) -> Result<Vec<Vec<u32>>, String> {
+    let n = rules.names.len();
+    let mut table = vec![vec![0; n]; n];
+    for (opponent, you) in rounds {
+        let opponent = opponent_move(rules, *opponent)?;
+        let letter = your_symbol(rules, *you)?;
+        for (you, total) in table[letter].iter_mut().enumerate() {
+            *total += score(rules, (opponent, you));
+        }
+    }
+    return Ok(table);
+}
+
+/// Highest scoring assignment of letters to moves, found over the subsets of
+/// used moves instead of all permutations. Among equal totals it picks the
+/// one `permutations` lists first.
+fn best_assignment(table: &Vec<Vec<u32>>) -> (Vec<Move>, u32) {
+    let n = table.len();
+    let full = (1usize << n) - 1;
+    // best[used] is the most the letters after the first |used| ones can
+    // score with the moves not in `used`
+    let mut best = vec![0; full + 1];
+    for used in (0..full).rev() {
+        let letter = used.count_ones() as usize;
+        best[used] = (0..n)
+            .filter(|m| used & (1 << m) == 0)
+            .map(|m| table[letter][m] + best[used | (1 << m)])
+            .max()
+            .unwrap();
+    }
+
+    let mut moves = Vec::new();
+    let mut used = 0;
+    for scores in table {
+        let m = (0..n)
+            .find(|m| used & (1 << m) == 0 && scores[*m] + best[used | (1 << m)] == best[used])
+            .unwrap();
+        moves.push(m);
+        used |= 1 << m;
+    }
+    return (moves, best[0]);
+}
+
+/// The mapping the guide most likely encodes. A guide is written to help, so
+/// this is the reading that scores highest; ties go to the puzzle's readings.
+fn likely_mapping(rules: &Rules, rounds: &Vec<Round>) -> Result<(Mapping, u32), String> {
+    let (moves, _) = best_assignment(&letter_scores(rules, rounds)?);
+    // in the order `mappings` lists them, with the best assignment standing
+    // in for all other move mappings
+    let mut candidates = vec![Mapping::Moves((0..rules.names.len()).collect())];
+    let mut outcomes = Vec::new();
+    if rules.your_symbols.len() == 3 {
+        outcomes = mappings(rules)
+            .into_iter()
+            .filter(|m| matches!(m, Mapping::Outcomes(_)))
+            .collect();
+        candidates.push(outcomes.remove(0));
+    }
+    candidates.push(Mapping::Moves(moves));
+    candidates.extend(outcomes);
+
+    let mut likely: Option<(Mapping, u32)> = None;
+    for mapping in candidates {
+        let total = score_with(rules, &mapping, rounds)?;
+        if likely.as_ref().is_none_or(|(_, best)| total > *best) {
+            likely = Some((mapping, total));
+        }
+    }
+    return Ok(likely.unwrap());
+}
+
+fn analyse(rules: &Rules, rounds: &Vec<Round>) -> Result<String, String> {
+    let mut report = vec![format!("best possible = {}", best_score(rules, rounds)?)];
+    let (likely, total) = likely_mapping(rules, rounds)?;
+    report.push(format!(
+        "likely mapping = {} ({})",
+        likely.describe(rules),
+        total
+    ));
+    match distribution(rules, rounds) {
+        Ok(distribution) => {
+            for (mapping, total) in distribution {
+                report.push(format!("{:>8} {}", total, mapping.describe(rules)));
+            }
+        }
+        Err(_) => report.push(format!(
+            "mappings are only listed for up to {} moves",
+            MAX_LISTED
+        )),
+    }
+    return Ok(report.join("\n"));
+}
+
+// `day02 rpsls` or `day02 <n>` for an n-move cyclic game, classic otherwise.
+// A trailing `analyse` prints how the guide scores under every mapping.
+fn rules_from_args() -> Rules {
+    match env::args().nth(1).as_deref() {
+        None | Some("classic") | Some("analyse") => Rules::classic(),
+        Some("rpsls") => Rules::rpsls(),
+        Some(n) => {
+            let n: usize = n.parse().expect("expected rpsls, classic or a move count");
+            let names: Vec<String> = (0..n).map(|i| format!("move{}", i)).collect();
+            let names: Vec<&str> = names.iter().map(|m| m.as_str()).collect();
+            Rules::cyclic(&names).unwrap_or_else(|e| panic!("Bad rules: {}", e))
+        }
+    }
 }
 
 fn main() {
-    let parsed = parse(lines());
-    println!("first = {}", first(&parsed));
-    println!("second = {}", second(&parsed));
+    let rules = rules_from_args();
+    let parsed = measured("parse", || cached("day02", &lines(), |l| parse(l.clone())));
+    println!("first = {}", measured("first", || first(&rules, &parsed)));
+    println!(
+        "second = {}",
+        measured("second", || second(&rules, &parsed))
+    );
+    if env::args().any(|a| a == "analyse") {
+        match analyse(&rules, &parsed) {
+            Ok(report) => println!("{}", report),
+            Err(e) => eprintln!("error: {}", e),
+        }
+    }
+    report_total();
 }
 
 #[cfg(test)]
@@ -108,11 +483,110 @@ C Z";
 
     #[test]
     fn test_first() {
-        assert_eq!(15, first(&test_input()));
+        assert_eq!(15, first(&Rules::classic(), &test_input()));
     }
 
     #[test]
     fn test_second() {
-        assert_eq!(12, second(&test_input()));
+        assert_eq!(12, second(&Rules::classic(), &test_input()));
+    }
+
+    #[test]
+    fn test_rules() {
+        let classic = Rules::classic();
+        assert_eq!(Outcome::Win, classic.outcome(0, 1));
+        assert_eq!(Outcome::Loose, classic.outcome(0, 2));
+        assert_eq!(Outcome::Draw, classic.outcome(2, 2));
+
+        assert!(Rules::cyclic(&["a", "b", "c", "d"]).is_err());
+        assert_eq!(
+            Some("1 moves cannot both win and lose".to_string()),
+            Rules::cyclic(&["a"]).err()
+        );
+        assert!(Rules::new(&["a", "b"], &[("a", "b"), ("b", "a")]).is_err());
+        assert!(Rules::new(&["a", "b", "c"], &[("a", "b"), ("a", "c"), ("b", "c")]).is_err());
+
+        let rpsls = Rules::rpsls();
+        let seven = Rules::cyclic(&["a", "b", "c", "d", "e", "f", "g"]).unwrap();
+        for rules in [&rpsls, &seven] {
+            let n = rules.names.len();
+            for m in 0..n {
+                let wins = (0..n).filter(|o| rules.outcome(*o, m) == Outcome::Win);
+                assert_eq!((n - 1) / 2, wins.count());
+            }
+        }
+    }
+
+    #[test]
+    fn test_variants() {
+        let rpsls = Rules::rpsls();
+        // spock vs lizard: win (6 + 4), rock vs paper: win (6 + 2), paper vs paper: draw (3 + 2)
+        let guide = parse(vec![
+            "E Y".to_string(),
+            "A W".to_string(),
+            "B W".to_string(),
+        ]);
+        assert_eq!(23, first(&rpsls, &guide));
+        // lose to spock with scissors (0 + 3), draw rock (3 + 1), beat paper with lizard (6 + 4)
+        let guide = parse(vec![
+            "E X".to_string(),
+            "A Y".to_string(),
+            "B Z".to_string(),
+        ]);
+        assert_eq!(17, second(&rpsls, &guide));
+
+        let bad = parse(vec!["F X".to_string()]);
+        assert_eq!(
+            Err("unknown opponent move F".to_string()),
+            play_as_outcomes(&rpsls, &bad)
+        );
+    }
+
+    #[test]
+    fn test_analysis() {
+        let rules = Rules::classic();
+        // paper, rock and scissors each beat with their counter: 8 + 7 + 9
+        assert_eq!(Ok(24), best_score(&rules, &test_input()));
+
+        let distribution = distribution(&rules, &test_input()).unwrap();
+        assert_eq!(12, distribution.len());
+        assert!(distribution.contains(&(Mapping::Moves(vec![0, 1, 2]), 15)));
+        assert!(distribution.contains(&(
+            Mapping::Outcomes(vec![Outcome::Loose, Outcome::Draw, Outcome::Win]),
+            12
+        )));
+        assert!(distribution.iter().all(|(_, total)| *total <= 24));
+
+        let (likely, total) = likely_mapping(&rules, &test_input()).unwrap();
+        assert_eq!(24, total);
+        assert_eq!("X=scissors Y=paper Z=rock", likely.describe(&rules));
+    }
+
+    #[test]
+    fn test_large_analysis() {
+        let names = ["a", "b", "c", "d", "e"];
+        let rules = Rules::cyclic(&names).unwrap();
+        let guide = parse(
+            ["A V", "B W", "C X", "D Y", "E Z", "A W", "C V", "E V"]
+                .iter()
+                .map(|l| l.to_string())
+                .collect(),
+        );
+        let listed = distribution(&rules, &guide).unwrap();
+        assert_eq!(120, listed.len());
+        assert_eq!(listed[0], likely_mapping(&rules, &guide).unwrap());
+
+        // far too many mappings to list, but still analysed
+        let names: Vec<String> = (0..13).map(|i| format!("move{}", i)).collect();
+        let names: Vec<&str> = names.iter().map(|m| m.as_str()).collect();
+        let rules = Rules::cyclic(&names).unwrap();
+        let guide = parse(vec!["A N".to_string(), "M Z".to_string()]);
+        assert!(distribution(&rules, &guide).is_err());
+        let report = analyse(&rules, &guide).unwrap();
+        assert!(report.ends_with("mappings are only listed for up to 7 moves"));
+        assert_eq!(
+            best_score(&rules, &guide),
+            likely_mapping(&rules, &guide).map(|(_, t)| t)
+        );
     }
 }
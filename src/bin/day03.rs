@@ -1,61 +1,291 @@
-use std::collections::HashSet;
+use std::{collections::HashMap, env, fs};
 
-use aoc_2022_rust::aoc::input::lines;
+use aoc_2022_rust::aoc::{
+    input::lines,
+    memory::{measured, report_total},
+};
 
-fn to_set(s: &str) -> HashSet<char> {
-    return s.chars().collect();
+/// Set of items as a bit mask, bit i for the i-th item of the alphabet,
+/// spread over as many words as the alphabet needs.
+#[derive(Clone, Debug, PartialEq)]
+struct Items(Vec<u64>);
+
+impl Items {
+    fn empty(size: usize) -> Items {
+        Items(vec![0; size.div_ceil(64)])
+    }
+
+    fn full(size: usize) -> Items {
+        let mut items = Items::empty(size);
+        for i in 0..size {
+            items.insert(i);
+        }
+        return items;
+    }
+
+    fn insert(&mut self, i: usize) {
+        self.0[i / 64] |= 1 << (i % 64);
+    }
+
+    fn contains(&self, i: usize) -> bool {
+        self.0[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    fn intersection(mut self, other: &Items) -> Items {
+        for (word, other) in self.0.iter_mut().zip(&other.0) {
+            *word &= other;
+        }
+        return self;
+    }
 }
 
-fn find_duplicate(rucksack: &String) -> char {
-    let (left, right) = rucksack.split_at(rucksack.len() / 2);
+#[derive(Debug, PartialEq)]
+enum RucksackError {
+    // rucksack index and the item that is not in the alphabet
+    UnknownItem(usize, char),
+    DuplicateItem(char),
+    // rucksack index and its length, which does not split into equal compartments
+    UnevenCompartments(usize, usize),
+    EmptyRucksack(usize),
+    // zero compartments per rucksack or rucksacks per group
+    ZeroCount,
+    // number of rucksacks left over after the last full group
+    IncompleteGroup(usize),
+    // index of the rucksack or group and what it has in common
+    NoCommonItem(usize),
+    MultipleCommonItems(usize, Vec<char>),
+}
 
-    let left_elements = to_set(left);
-    let right_elements = to_set(right);
+/// The items that may appear in rucksacks and what each one is worth.
+struct Alphabet {
+    items: Vec<char>,
+    priorities: Vec<u32>,
+    positions: HashMap<char, usize>,
+}
 
-    *left_elements
-        .iter()
-        .filter(|e| right_elements.contains(e))
-        .next()
-        .unwrap()
+impl Alphabet {
+    /// Explicit priority table. Items keep the given order, which is also the
+    /// order common items are reported in.
+    fn new(table: &[(char, u32)]) -> Result<Alphabet, RucksackError> {
+        let mut positions = HashMap::new();
+        for (i, (item, _)) in table.iter().enumerate() {
+            if positions.insert(*item, i).is_some() {
+                return Err(RucksackError::DuplicateItem(*item));
+            }
+        }
+
+        return Ok(Alphabet {
+            items: table.iter().map(|(i, _)| *i).collect(),
+            priorities: table.iter().map(|(_, p)| *p).collect(),
+            positions,
+        });
+    }
+
+    /// Items worth 1, 2, 3, ... in the order they are listed.
+    fn ordered(items: &str) -> Result<Alphabet, RucksackError> {
+        let table: Vec<(char, u32)> = items.chars().zip(1..).collect();
+        return Alphabet::new(&table);
+    }
+
+    /// The puzzle's a-z, then A-Z.
+    fn letters() -> Alphabet {
+        let items: String = ('a'..='z').chain('A'..='Z').collect();
+        return Alphabet::ordered(&items).unwrap();
+    }
+
+    /// Reads `<item> <priority>` lines.
+    fn parse_table(table: &str) -> Result<Alphabet, String> {
+        let mut entries = Vec::new();
+        for (i, line) in table
+            .lines()
+            .enumerate()
+            .filter(|(_, l)| !l.trim().is_empty())
+        {
+            let entry = match line.split_whitespace().collect::<Vec<&str>>().as_slice() {
+                [item, priority] if item.chars().count() == 1 => priority
+                    .parse()
+                    .ok()
+                    .map(|p| (item.chars().next().unwrap(), p)),
+                _ => None,
+            };
+            entries.push(entry.ok_or(format!("line {}: expected <item> <priority>", i + 1))?);
+        }
+        return Alphabet::new(&entries).map_err(|e| format!("{:?}", e));
+    }
+
+    fn index(&self, item: char) -> Option<usize> {
+        self.positions.get(&item).copied()
+    }
+
+    fn priority(&self, item: char) -> u32 {
+        return self.priorities[self.index(item).unwrap()];
+    }
+
+    fn to_mask(&self, rucksack: usize, items: &[char]) -> Result<Items, RucksackError> {
+        let mut mask = Items::empty(self.items.len());
+        for item in items {
+            let index = self
+                .index(*item)
+                .ok_or(RucksackError::UnknownItem(rucksack, *item))?;
+            mask.insert(index);
+        }
+        return Ok(mask);
+    }
+
+    fn to_items(&self, mask: Items) -> Vec<char> {
+        return (0..self.items.len())
+            .filter(|i| mask.contains(*i))
+            .map(|i| self.items[i])
+            .collect();
+    }
+}
+
+/// Checks that every item of every rucksack belongs to the alphabet.
+fn validate(alphabet: &Alphabet, rucksacks: &[String]) -> Result<(), RucksackError> {
+    for (i, rucksack) in rucksacks.iter().enumerate() {
+        if let Some(item) = rucksack.chars().find(|c| alphabet.index(*c).is_none()) {
+            return Err(RucksackError::UnknownItem(i, item));
+        }
+    }
+    return Ok(());
 }
 
-fn find_badge(rucksacks: &[String]) -> char {
-    let left = to_set(rucksacks[0].as_str());
-    let middle = to_set(rucksacks[1].as_str());
-    let right = to_set(rucksacks[2].as_str());
+fn common<I: Iterator<Item = Items>>(alphabet: &Alphabet, masks: I) -> Items {
+    masks.fold(Items::full(alphabet.items.len()), |acc, mask| {
+        acc.intersection(&mask)
+    })
+}
 
-    *left
-        .iter()
-        .filter(|e| middle.contains(e) && right.contains(e))
-        .next()
-        .unwrap()
+/// Items present in every one of the `count` equally sized compartments of
+/// the `index`-th rucksack.
+fn find_duplicates(
+    alphabet: &Alphabet,
+    index: usize,
+    rucksack: &String,
+    count: usize,
+) -> Result<Vec<char>, RucksackError> {
+    let items: Vec<char> = rucksack.chars().collect();
+    if count == 0 {
+        return Err(RucksackError::ZeroCount);
+    }
+    if items.is_empty() {
+        return Err(RucksackError::EmptyRucksack(index));
+    }
+    if !items.len().is_multiple_of(count) {
+        return Err(RucksackError::UnevenCompartments(index, items.len()));
+    }
+
+    let mut masks = Vec::new();
+    for compartment in items.chunks(items.len() / count) {
+        masks.push(alphabet.to_mask(index, compartment)?);
+    }
+    return Ok(alphabet.to_items(common(alphabet, masks.into_iter())));
+}
+
+/// Items carried by every rucksack of the group starting at rucksack `first`.
+fn find_badges(
+    alphabet: &Alphabet,
+    first: usize,
+    group: &[String],
+) -> Result<Vec<char>, RucksackError> {
+    let mut masks = Vec::new();
+    for (i, rucksack) in group.iter().enumerate() {
+        let items: Vec<char> = rucksack.chars().collect();
+        masks.push(alphabet.to_mask(first + i, &items)?);
+    }
+    return Ok(alphabet.to_items(common(alphabet, masks.into_iter())));
+}
+
+fn single(index: usize, items: Vec<char>) -> Result<char, RucksackError> {
+    match items.as_slice() {
+        [] => Err(RucksackError::NoCommonItem(index)),
+        [item] => Ok(*item),
+        _ => Err(RucksackError::MultipleCommonItems(index, items)),
+    }
 }
 
-fn priority(item: char) -> u32 {
-    if item.is_ascii_lowercase() {
-        return (item as u32) - ('a' as u32) + 1;
-    } else {
-        return (item as u32) - ('A' as u32) + 27;
+fn sum_duplicates(
+    alphabet: &Alphabet,
+    rucksacks: &Vec<String>,
+    compartments: usize,
+) -> Result<u32, RucksackError> {
+    validate(alphabet, rucksacks)?;
+
+    let mut total = 0;
+    for (i, rucksack) in rucksacks.iter().enumerate() {
+        let duplicates = find_duplicates(alphabet, i, rucksack, compartments)?;
+        total += alphabet.priority(single(i, duplicates)?);
     }
+    return Ok(total);
 }
 
-fn first(rucksacks: &Vec<String>) -> u32 {
-    rucksacks.iter().map(find_duplicate).map(priority).sum()
+fn sum_badges(
+    alphabet: &Alphabet,
+    rucksacks: &Vec<String>,
+    group_size: usize,
+) -> Result<u32, RucksackError> {
+    validate(alphabet, rucksacks)?;
+    if group_size == 0 {
+        return Err(RucksackError::ZeroCount);
+    }
+    if !rucksacks.len().is_multiple_of(group_size) {
+        return Err(RucksackError::IncompleteGroup(rucksacks.len() % group_size));
+    }
+
+    let mut total = 0;
+    for (i, group) in rucksacks.chunks(group_size).enumerate() {
+        let badges = find_badges(alphabet, i * group_size, group)?;
+        total += alphabet.priority(single(i, badges)?);
+    }
+    return Ok(total);
 }
 
-fn second(rucksacks: &Vec<String>) -> u32 {
-    rucksacks
-        .chunks(3)
-        .into_iter()
-        .map(find_badge)
-        .map(priority)
-        .sum()
+fn first(alphabet: &Alphabet, rucksacks: &Vec<String>) -> u32 {
+    match sum_duplicates(alphabet, rucksacks, 2) {
+        Ok(v) => v,
+        Err(e) => panic!("Bad rucksack: {:?}", e),
+    }
+}
+
+fn second(alphabet: &Alphabet, rucksacks: &Vec<String>) -> u32 {
+    match sum_badges(alphabet, rucksacks, 3) {
+        Ok(v) => v,
+        Err(e) => panic!("Bad group: {:?}", e),
+    }
+}
+
+// `day03 alphabet <items>` ranks items in the given order, `day03 table <file>`
+// reads explicit priorities; the puzzle's letters otherwise.
+fn alphabet_from_args() -> Alphabet {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args
+        .iter()
+        .map(|a| a.as_str())
+        .collect::<Vec<&str>>()
+        .as_slice()
+    {
+        [] => Alphabet::letters(),
+        ["alphabet", items] => Alphabet::ordered(items).expect("bad alphabet"),
+        ["table", path] => {
+            let table = fs::read_to_string(path).expect("cannot read priority table");
+            Alphabet::parse_table(&table).expect("bad priority table")
+        }
+        _ => panic!("usage: day03 [alphabet <items> | table <file>]"),
+    }
 }
 
 fn main() {
-    let parsed = lines();
-    println!("first = {}", first(&parsed));
-    println!("second = {}", second(&parsed));
+    let alphabet = alphabet_from_args();
+    let parsed = measured("parse", lines);
+    println!(
+        "first = {}",
+        measured("first", || first(&alphabet, &parsed))
+    );
+    println!(
+        "second = {}",
+        measured("second", || second(&alphabet, &parsed))
+    );
+    report_total();
 }
 
 #[cfg(test)]
@@ -71,12 +301,110 @@ CrZsJsPPZsGzwwsLwLmpwMDw";
     #[test]
     fn test_first() {
         let rucksacks: Vec<String> = INPUT.lines().map(|l| l.trim().to_string()).collect();
-        assert_eq!(157, first(&rucksacks));
+        assert_eq!(157, first(&Alphabet::letters(), &rucksacks));
     }
 
     #[test]
     fn test_second() {
         let rucksacks: Vec<String> = INPUT.lines().map(|l| l.trim().to_string()).collect();
-        assert_eq!(70, second(&rucksacks));
+        assert_eq!(70, second(&Alphabet::letters(), &rucksacks));
+    }
+
+    #[test]
+    fn test_groups() {
+        let letters = Alphabet::letters();
+        let rucksacks: Vec<String> = INPUT.lines().map(|l| l.trim().to_string()).collect();
+        let pairs: Vec<String> = ["abc", "cde", "xcy", "zcw"].map(String::from).to_vec();
+        assert_eq!(Ok(3 + 3), sum_badges(&letters, &pairs, 2));
+        assert_eq!(
+            Err(RucksackError::MultipleCommonItems(
+                0,
+                vec!['f', 'r', 's', 'F', 'M']
+            )),
+            sum_badges(&letters, &rucksacks[0..2].to_vec(), 2)
+        );
+        assert_eq!(Ok(vec!['r']), find_badges(&letters, 0, &rucksacks[0..3]));
+        assert_eq!(
+            Err(RucksackError::IncompleteGroup(2)),
+            sum_badges(&letters, &rucksacks, 4)
+        );
+        assert_eq!(
+            Ok(vec!['s']),
+            find_duplicates(&letters, 0, &"abcsdefsghisjkl".to_string(), 3)
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let letters = Alphabet::letters();
+        assert_eq!(
+            Err(RucksackError::MultipleCommonItems(0, vec!['a', 'b'])),
+            sum_duplicates(&letters, &vec!["abab".to_string()], 2)
+        );
+        assert_eq!(
+            Err(RucksackError::NoCommonItem(1)),
+            sum_duplicates(&letters, &vec!["abcb".to_string(), "abcd".to_string()], 2)
+        );
+        assert_eq!(
+            Err(RucksackError::UnevenCompartments(0, 3)),
+            sum_duplicates(&letters, &vec!["abc".to_string()], 2)
+        );
+        assert_eq!(
+            Err(RucksackError::UnknownItem(1, '1')),
+            sum_duplicates(&letters, &vec!["aa".to_string(), "a1a1".to_string()], 2)
+        );
+        assert_eq!(
+            Err(RucksackError::EmptyRucksack(1)),
+            sum_duplicates(&letters, &vec!["aa".to_string(), String::new()], 2)
+        );
+        assert_eq!(
+            Err(RucksackError::ZeroCount),
+            sum_duplicates(&letters, &vec!["aa".to_string()], 0)
+        );
+        assert_eq!(
+            Err(RucksackError::ZeroCount),
+            sum_badges(&letters, &vec!["aa".to_string()], 0)
+        );
+    }
+
+    #[test]
+    fn test_alphabets() {
+        let codes = Alphabet::ordered("0123456789жщы").unwrap();
+        let rucksacks: Vec<String> = ["1ж2ж", "щ3щ4", "ы9ы0"].map(String::from).to_vec();
+        assert_eq!(Ok(11 + 12 + 13), sum_duplicates(&codes, &rucksacks, 2));
+        assert_eq!(
+            Err(RucksackError::UnknownItem(0, 'a')),
+            sum_badges(&codes, &vec!["a".to_string()], 1)
+        );
+
+        let table = Alphabet::parse_table("x 100\ny 5\n\nж 7\n").unwrap();
+        assert_eq!(
+            Ok(100 + 7),
+            sum_badges(
+                &table,
+                &["xy", "xж", "жy", "жж"].map(String::from).to_vec(),
+                2
+            )
+        );
+        assert_eq!(
+            Err("line 2: expected <item> <priority>".to_string()),
+            Alphabet::parse_table("x 1\nyy 2").map(|_| ())
+        );
+
+        assert_eq!(
+            Err(RucksackError::DuplicateItem('a')),
+            Alphabet::ordered("abca").map(|_| ())
+        );
+        let large: String = ('a'..='z')
+            .chain('A'..='Z')
+            .chain('0'..='9')
+            .chain("+-*/".chars())
+            .collect();
+        let large = Alphabet::ordered(&(large + "жщы")).unwrap();
+        assert_eq!(69, large.items.len());
+        let rucksacks: Vec<String> = ["aжbж", "щ+щ-", "ыZы/"].map(String::from).to_vec();
+        assert_eq!(Ok(67 + 68 + 69), sum_duplicates(&large, &rucksacks, 2));
+        let group: Vec<String> = ["aыb", "ыcd"].map(String::from).to_vec();
+        assert_eq!(Ok(69), sum_badges(&large, &group, 2));
     }
 }
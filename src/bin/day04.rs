@@ -1,17 +1,23 @@
-use aoc_2022_rust::aoc::input::lines;
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet, BinaryHeap},
+    env,
+};
 
-struct Interval {
-    begin: u32,
-    end: u32,
-}
+use aoc_2022_rust::aoc::{
+    cache::cached,
+    input::{file_lines, lines},
+    interval::{max_concurrency, Interval, IntervalSet},
+    memory::{measured, report_total},
+    repl::{self, Repl},
+};
+
+type Pair = (Interval, Interval);
 
-fn parse(input: &Vec<String>) -> Vec<(Interval, Interval)> {
+fn parse(input: &Vec<String>) -> Vec<Pair> {
     let to_interval = |s: &str| -> Interval {
         let parts: Vec<&str> = s.split('-').collect();
-        return Interval {
-            begin: parts[0].parse().unwrap(),
-            end: parts[1].parse().unwrap(),
-        };
+        return Interval::new(parts[0].parse().unwrap(), parts[1].parse().unwrap());
     };
 
     input
@@ -23,37 +29,175 @@ fn parse(input: &Vec<String>) -> Vec<(Interval, Interval)> {
         .collect()
 }
 
-fn contains((first, second): &(Interval, Interval)) -> bool {
-    let contains = |a: &Interval, b: &Interval| -> bool {
-        return a.begin <= b.begin && a.end >= b.end;
-    };
-    return contains(first, second) || contains(second, first);
+fn contains((first, second): &Pair) -> bool {
+    return first.contains(second) || second.contains(first);
 }
 
-fn overlaps((first, second): &(Interval, Interval)) -> bool {
-    let overlaps = |a: &Interval, b: &Interval| -> bool {
-        return a.begin <= b.begin && a.end >= b.begin;
-    };
-    return overlaps(first, second) || overlaps(second, first);
+fn overlaps((first, second): &Pair) -> bool {
+    return first.overlaps(second);
+}
+
+fn assignments(pairs: &Vec<Pair>) -> Vec<Interval> {
+    return pairs.iter().flat_map(|(a, b)| [*a, *b]).collect();
 }
 
-fn first(intervals: &Vec<(Interval, Interval)>) -> usize {
+/// Sections between the lowest and highest assigned one that nobody cleans.
+fn uncovered(pairs: &Vec<Pair>) -> IntervalSet {
+    return assignments(pairs)
+        .into_iter()
+        .collect::<IntervalSet>()
+        .gaps();
+}
+
+/// Largest number of elves assigned to the same section.
+fn busiest(pairs: &Vec<Pair>) -> usize {
+    return max_concurrency(&assignments(pairs));
+}
+
+fn first(intervals: &Vec<Pair>) -> usize {
     return intervals.iter().filter(|i| contains(*i)).count();
 }
 
-fn second(intervals: &Vec<(Interval, Interval)>) -> usize {
+fn second(intervals: &Vec<Pair>) -> usize {
     return intervals.iter().filter(|i| overlaps(*i)).count();
 }
 
+/// Index into `assignments`, two per input line.
+type Elf = usize;
+
+fn label(elf: Elf) -> String {
+    return format!("{}{}", elf / 2 + 1, if elf.is_multiple_of(2) { 'a' } else { 'b' });
+}
+
+#[derive(Debug, PartialEq)]
+enum Relation {
+    Overlaps,
+    Contains,
+    Equal,
+}
+
+/// Two elves whose assignments share sections, starting at `section`.
+/// For `Contains`, `outer` is the one with the larger assignment.
+#[derive(Debug, PartialEq)]
+struct Conflict {
+    section: i64,
+    outer: Elf,
+    inner: Elf,
+    relation: Relation,
+}
+
+/// Every pair of elves with overlapping assignments, across the whole crew,
+/// keyed by the first section the two share.
+///
+/// Sweeps the assignments by start section while keeping the ones still
+/// running, so the cost is O(n log n) plus the number of conflicts.
+fn conflicts(pairs: &Vec<Pair>) -> BTreeMap<i64, Vec<Conflict>> {
+    let assignments = assignments(pairs);
+    let mut order: Vec<Elf> = (0..assignments.len()).collect();
+    order.sort_by_key(|elf| (assignments[*elf].begin, Reverse(assignments[*elf].end)));
+
+    let mut report: BTreeMap<i64, Vec<Conflict>> = BTreeMap::new();
+    // running assignments, by end section
+    let mut active: BTreeSet<(i64, Elf)> = BTreeSet::new();
+    let mut ends: BinaryHeap<Reverse<(i64, Elf)>> = BinaryHeap::new();
+    for elf in order {
+        let current = assignments[elf];
+        while let Some(Reverse((end, other))) = ends.peek().copied() {
+            if end >= current.begin {
+                break;
+            }
+            ends.pop();
+            active.remove(&(end, other));
+        }
+
+        // everything still running started no later and ends at or after
+        // `current.begin`, so it overlaps from `current.begin` on
+        for (_, other) in &active {
+            let earlier = assignments[*other];
+            let relation = if earlier == current {
+                Relation::Equal
+            } else if earlier.contains(&current) {
+                Relation::Contains
+            } else {
+                Relation::Overlaps
+            };
+            report.entry(current.begin).or_default().push(Conflict {
+                section: current.begin,
+                outer: *other,
+                inner: elf,
+                relation,
+            });
+        }
+
+        active.insert((current.end, elf));
+        ends.push(Reverse((current.end, elf)));
+    }
+    return report;
+}
+
+fn conflict_report(pairs: &Vec<Pair>) -> String {
+    let mut lines = Vec::new();
+    for (section, conflicts) in conflicts(pairs) {
+        let described: Vec<String> = conflicts
+            .iter()
+            .map(|c| {
+                let verb = match c.relation {
+                    Relation::Overlaps => "overlaps",
+                    Relation::Contains => "contains",
+                    Relation::Equal => "equals",
+                };
+                format!("{} {} {}", label(c.outer), verb, label(c.inner))
+            })
+            .collect();
+        lines.push(format!("section {}: {}", section, described.join(", ")));
+    }
+    return lines.join("\n");
+}
+
+fn repl(pairs: &Vec<Pair>) -> Repl<'_, Vec<Pair>> {
+    return Repl::new(pairs)
+        .command("first", "", |pairs, _| Ok(first(pairs).to_string()))
+        .command("second", "", |pairs, _| Ok(second(pairs).to_string()))
+        .command("uncovered", "", |pairs, _| {
+            let gaps: Vec<String> = uncovered(pairs)
+                .intervals()
+                .iter()
+                .map(|i| format!("{}-{}", i.begin, i.end))
+                .collect();
+            Ok(gaps.join(" "))
+        })
+        .command("busiest", "", |pairs, _| Ok(busiest(pairs).to_string()))
+        .command("conflicts", "", |pairs, _| Ok(conflict_report(pairs)))
+        .command("covered", "<section>", |pairs, args| {
+            let section: i64 = repl::arg(args, 0, "section")?;
+            let count = assignments(pairs)
+                .iter()
+                .filter(|i| i.contains_point(section))
+                .count();
+            Ok(count.to_string())
+        });
+}
+
 fn main() {
-    let parsed = parse(&lines());
-    println!("first = {}", first(&parsed));
-    println!("second = {}", second(&parsed));
+    if let Some(path) = repl::requested() {
+        let parsed = cached("day04", &file_lines(&path), parse);
+        return repl(&parsed).run_stdio();
+    }
+
+    let parsed = measured("parse", || cached("day04", &lines(), parse));
+    if env::args().nth(1).as_deref() == Some("conflicts") {
+        println!("{}", measured("conflicts", || conflict_report(&parsed)));
+        return report_total();
+    }
+    println!("first = {}", measured("first", || first(&parsed)));
+    println!("second = {}", measured("second", || second(&parsed)));
+    report_total();
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
     const INPUT: &str = "2-4,6-8
 2-3,4-5
 5-7,7-9
@@ -72,4 +216,72 @@ mod tests {
         let intervals = parse(&INPUT.lines().map(|l| l.to_string()).collect());
         assert_eq!(4, second(&intervals));
     }
+
+    #[test]
+    fn test_coverage() {
+        let pairs = parse(&INPUT.lines().map(|l| l.to_string()).collect());
+        assert!(uncovered(&pairs).is_empty());
+        assert_eq!(8, busiest(&pairs));
+
+        let sparse = parse(&vec!["1-2,8-9".to_string(), "3-3,12-12".to_string()]);
+        assert_eq!(
+            &[Interval::new(4, 7), Interval::new(10, 11)],
+            uncovered(&sparse).intervals()
+        );
+        assert_eq!(1, busiest(&sparse));
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let pairs = parse(&vec![
+            "2-4,6-8".to_string(),
+            "3-3,4-7".to_string(),
+            "6-8,9-9".to_string(),
+        ]);
+        let report = conflicts(&pairs);
+        assert_eq!(vec![&3, &4, &6], report.keys().collect::<Vec<&i64>>());
+        assert_eq!(
+            vec![Conflict {
+                section: 3,
+                outer: 0,
+                inner: 2,
+                relation: Relation::Contains
+            }],
+            report[&3]
+        );
+        assert_eq!(
+            "section 3: 1a contains 2a\nsection 4: 1a overlaps 2b\nsection 6: 2b overlaps 1b, 2b overlaps 3a, 1b equals 3a",
+            conflict_report(&pairs)
+        );
+
+        // every in-pair overlap shows up in the crew-wide report
+        let pairs = parse(&INPUT.lines().map(|l| l.to_string()).collect());
+        let report = conflicts(&pairs);
+        let in_pair = report
+            .values()
+            .flatten()
+            .filter(|c| c.outer / 2 == c.inner / 2)
+            .count();
+        assert_eq!(second(&pairs), in_pair);
+    }
+
+    #[test]
+    fn test_repl() {
+        let pairs = parse(&INPUT.lines().map(|l| l.to_string()).collect());
+        let repl = repl(&pairs);
+        assert_eq!(Some("2".to_string()), repl.execute("first"));
+        assert_eq!(Some("".to_string()), repl.execute("uncovered"));
+        assert_eq!(Some("8".to_string()), repl.execute("covered 6"));
+    }
+
+    fn interval() -> impl Strategy<Value = Interval> {
+        (1i64..100, 0i64..20).prop_map(|(begin, len)| Interval::new(begin, begin + len))
+    }
+
+    proptest! {
+        #[test]
+        fn contains_implies_overlaps(pair in (interval(), interval())) {
+            prop_assert!(!contains(&pair) || overlaps(&pair));
+        }
+    }
 }
@@ -1,118 +1,566 @@
-use std::{iter::Peekable, slice::Iter};
+use std::{env, fmt, process};
 
-use aoc_2022_rust::aoc::input::lines;
+use aoc_2022_rust::aoc::{
+    cache::cached,
+    input::lines,
+    memory::{measured, report_total},
+};
+use serde::{Deserialize, Serialize};
 
-type Stack = Vec<char>;
+type Crate = String;
+type Stack = Vec<Crate>;
 
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 struct Operation {
     from: usize,
     to: usize,
     count: usize,
 }
 
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "move {} from {} to {}", self.count, self.from, self.to)
+    }
+}
+
+#[derive(Deserialize, Serialize)]
 struct Input {
     stacks: Vec<Stack>,
     operations: Vec<Operation>,
 }
 
-fn parse(input: &Vec<String>) -> Input {
-    let parse_stacks = |it: &mut Peekable<Iter<String>>| -> Vec<Stack> {
-        let first = it.peek().unwrap();
-        // len = 4n - 1 -> n = (len + 1) / 4
-        let capacity = (first.len() + 1) / 4;
+#[derive(Debug, PartialEq)]
+enum LayoutError {
+    // no numbered label row, or no blank line after the drawing
+    MissingLabels,
+    // line number (1-based) and what is wrong with it
+    BadLabels(usize, String),
+    MalformedRow(usize, String),
+    UnalignedCrate(usize, Crate),
+    FloatingCrate(usize, Crate),
+    BadOperation(usize, String),
+}
 
-        let mut stacks: Vec<Stack> = Vec::with_capacity(capacity);
-        for _ in 0..capacity {
-            stacks.push(Stack::new());
+/// Splits a drawing row into `(first column, last column + 1, text)` tokens.
+fn tokens(line: &str) -> Vec<(usize, usize, String)> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut result = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] == ' ' {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < chars.len() && chars[i] != ' ' {
+            i += 1;
         }
+        result.push((start, i, chars[start..i].iter().collect()));
+    }
+    return result;
+}
 
-        for line in it {
-            if !line.contains('[') {
-                break;
-            }
+fn parse_labels(number: usize, line: &str) -> Result<Vec<(usize, usize)>, LayoutError> {
+    let labels = tokens(line);
+    if labels.is_empty() || line.contains('[') {
+        return Err(LayoutError::MissingLabels);
+    }
+    for (i, (_, _, label)) in labels.iter().enumerate() {
+        if label.parse::<usize>().ok() != Some(i + 1) {
+            return Err(LayoutError::BadLabels(
+                number,
+                format!("expected stack {}, found '{}'", i + 1, label),
+            ));
+        }
+    }
+    return Ok(labels.into_iter().map(|(b, e, _)| (b, e)).collect());
+}
 
-            for (i, el) in line.chars().skip(1).step_by(4).enumerate() {
-                if el != ' ' {
-                    stacks[i].push(el);
-                }
+/// Parses the drawing above the blank line. Crates are matched to the
+/// stack whose label they sit over, so neither the width of crates and
+/// labels nor trailing whitespace matters.
+fn parse_stacks(drawing: &[String]) -> Result<Vec<Stack>, LayoutError> {
+    let (label_row, rows) = drawing.split_last().ok_or(LayoutError::MissingLabels)?;
+    let columns = parse_labels(drawing.len(), label_row)?;
+
+    let mut stacks: Vec<Stack> = vec![Stack::new(); columns.len()];
+    // whether a stack already had an empty slot below the current row
+    let mut ended = vec![false; columns.len()];
+    for (i, row) in rows.iter().enumerate().rev() {
+        let number = i + 1;
+        let mut filled = vec![false; columns.len()];
+        for (begin, end, token) in tokens(row) {
+            if token.len() < 3 || !token.starts_with('[') || !token.ends_with(']') {
+                return Err(LayoutError::MalformedRow(number, token));
             }
+            let name = token[1..token.len() - 1].to_string();
+            let under: Vec<usize> = (0..columns.len())
+                .filter(|c| columns[*c].0 < end && begin < columns[*c].1)
+                .collect();
+            let stack = match under.as_slice() {
+                [stack] if !filled[*stack] => *stack,
+                _ => return Err(LayoutError::UnalignedCrate(number, name)),
+            };
+            if ended[stack] {
+                return Err(LayoutError::FloatingCrate(number, name));
+            }
+            filled[stack] = true;
+            stacks[stack].push(name);
         }
-
-        for stack in &mut stacks {
-            stack.reverse();
+        for stack in 0..columns.len() {
+            ended[stack] |= !filled[stack];
         }
+    }
+    return Ok(stacks);
+}
 
-        return stacks;
-    };
+fn parse_operation(number: usize, line: &str) -> Result<Operation, LayoutError> {
+    let bad = || LayoutError::BadOperation(number, line.to_string());
+    match line.split_whitespace().collect::<Vec<&str>>().as_slice() {
+        ["move", count, "from", from, "to", to] => Ok(Operation {
+            count: count.parse().map_err(|_| bad())?,
+            from: from.parse().map_err(|_| bad())?,
+            to: to.parse().map_err(|_| bad())?,
+        }),
+        _ => Err(bad()),
+    }
+}
 
-    let parse_operations = |it: &mut Peekable<Iter<String>>| -> Vec<Operation> {
-        let mut operations = Vec::new();
+fn parse_input(input: &Vec<String>) -> Result<Input, LayoutError> {
+    let blank = input
+        .iter()
+        .position(|l| l.trim().is_empty())
+        .ok_or(LayoutError::MissingLabels)?;
+    let stacks = parse_stacks(&input[..blank])?;
 
-        for line in it {
-            let parts: Vec<&str> = line.split(' ').collect();
-            let count: usize = parts[1].parse().unwrap();
-            let from: usize = parts[3].parse().unwrap();
-            let to: usize = parts[5].parse().unwrap();
-            operations.push(Operation { from, to, count });
+    let mut operations = Vec::new();
+    for (i, line) in input.iter().enumerate().skip(blank + 1) {
+        if !line.trim().is_empty() {
+            operations.push(parse_operation(i + 1, line)?);
         }
+    }
+    return Ok(Input { stacks, operations });
+}
 
-        return operations;
-    };
+fn parse(input: &Vec<String>) -> Input {
+    match parse_input(input) {
+        Ok(input) => input,
+        Err(e) => panic!("Bad drawing: {:?}", e),
+    }
+}
 
-    let mut it = input.iter().peekable();
+/// A crane model, i.e. the order in which moved crates land.
+trait Crane {
+    /// Rearranges `lifted`, the crates taken off a stack listed bottom to
+    /// top, into the order they end up in on the target stack.
+    fn place(&self, lifted: Stack) -> Stack;
 
-    let stacks = parse_stacks(&mut it);
+    /// Rejects operations the model cannot perform.
+    fn check(&self, _op: &Operation) -> Result<(), String> {
+        Ok(())
+    }
+}
 
-    it.next(); // skip empty line
+/// Moves one crate at a time.
+struct CrateMover9000;
 
-    let operations = parse_operations(&mut it);
+/// Moves all crates of an operation at once.
+struct CrateMover9001;
 
-    return Input { stacks, operations };
+/// Moves at most `capacity` crates at once, topmost batch first.
+struct LimitedLift {
+    capacity: usize,
 }
 
-fn first(input: &Input) -> String {
+impl Crane for CrateMover9000 {
+    fn place(&self, mut lifted: Stack) -> Stack {
+        lifted.reverse();
+        return lifted;
+    }
+}
 
-    let mut stacks = input.stacks.clone();
+impl Crane for CrateMover9001 {
+    fn place(&self, lifted: Stack) -> Stack {
+        return lifted;
+    }
+}
 
-    for op in &input.operations {
-        for _ in 0..op.count {
-            let c = stacks[op.from - 1].pop().unwrap();
-            stacks[op.to - 1].push(c);
+impl Crane for LimitedLift {
+    fn place(&self, lifted: Stack) -> Stack {
+        return lifted.rchunks(self.capacity).flatten().cloned().collect();
+    }
+
+    fn check(&self, _op: &Operation) -> Result<(), String> {
+        if self.capacity == 0 {
+            return Err("crane cannot lift anything".to_string());
         }
+        return Ok(());
     }
+}
 
-    return stacks.iter().map(|s| s.last().unwrap()).collect::<String>();
+#[derive(Debug, PartialEq)]
+enum CraneError {
+    // instruction number (1-based), the instruction and what is wrong with it
+    NoSuchStack(usize, Operation, usize),
+    NotEnoughCrates(usize, Operation, usize),
+    Refused(usize, Operation, String),
 }
 
-fn second(input: &Input) -> String {
+fn apply(
+    crane: &dyn Crane,
+    stacks: &mut Vec<Stack>,
+    step: usize,
+    op: &Operation,
+) -> Result<(), CraneError> {
+    for stack in [op.from, op.to] {
+        if stack == 0 || stack > stacks.len() {
+            return Err(CraneError::NoSuchStack(step, op.clone(), stack));
+        }
+    }
+    let available = stacks[op.from - 1].len();
+    if available < op.count {
+        return Err(CraneError::NotEnoughCrates(step, op.clone(), available));
+    }
+    crane
+        .check(op)
+        .map_err(|reason| CraneError::Refused(step, op.clone(), reason))?;
+
+    // crates put back where they were taken from keep their order
+    if op.from == op.to {
+        return Ok(());
+    }
+
+    let lifted = stacks[op.from - 1].split_off(available - op.count);
+    stacks[op.to - 1].extend(crane.place(lifted));
+    return Ok(());
+}
+
+/// Runs all operations, stopping at the first one that cannot be carried out.
+fn simulate(crane: &dyn Crane, input: &Input) -> Result<Vec<Stack>, CraneError> {
     let mut stacks = input.stacks.clone();
+    for (i, op) in input.operations.iter().enumerate() {
+        apply(crane, &mut stacks, i + 1, op)?;
+    }
+    return Ok(stacks);
+}
+
+/// Carries out the operations one by one, yielding the stacks after each.
+struct Replay<'a> {
+    crane: &'a dyn Crane,
+    operations: &'a [Operation],
+    stacks: Vec<Stack>,
+    step: usize,
+    failed: bool,
+}
 
-    for op in &input.operations {
-        let mut moved: Vec<char> = Vec::with_capacity(op.count);
-        for _ in 0..op.count {
-            moved.push(stacks[op.from - 1].pop().unwrap())
+impl<'a> Replay<'a> {
+    fn new(crane: &'a dyn Crane, input: &'a Input) -> Replay<'a> {
+        return Replay {
+            crane,
+            operations: &input.operations,
+            stacks: input.stacks.clone(),
+            step: 0,
+            failed: false,
+        };
+    }
+}
+
+impl<'a> Iterator for Replay<'a> {
+    type Item = Result<(&'a Operation, Vec<Stack>), CraneError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.step == self.operations.len() {
+            return None;
         }
-        for _ in 0..op.count {
-            stacks[op.to - 1].push(moved.pop().unwrap());
+
+        let op = &self.operations[self.step];
+        self.step += 1;
+        if let Err(e) = apply(self.crane, &mut self.stacks, self.step, op) {
+            self.failed = true;
+            return Some(Err(e));
         }
+        return Some(Ok((op, self.stacks.clone())));
+    }
+}
+
+/// The initial drawing followed by each operation and the drawing after it,
+/// up to the first operation that fails.
+fn replay(crane: &dyn Crane, input: &Input) -> (String, Option<CraneError>) {
+    let mut output = render(&input.stacks);
+    for step in Replay::new(crane, input) {
+        match step {
+            Ok((op, stacks)) => output += &format!("\n{}\n{}", op, render(&stacks)),
+            Err(e) => return (output, Some(e)),
+        }
+    }
+    return (output, None);
+}
+
+// Crate whose label is not known, drawn as `[?]`.
+const UNKNOWN: &str = "?";
+
+/// Inverse of `crane.place`: the order the crates had before being moved.
+fn unplace(crane: &dyn Crane, placed: Stack) -> Stack {
+    let order = crane.place((0..placed.len()).map(|i| i.to_string()).collect());
+    let mut lifted = vec![Crate::new(); placed.len()];
+    for (crate_, position) in placed.into_iter().zip(order) {
+        lifted[position.parse::<usize>().unwrap()] = crate_;
+    }
+    return lifted;
+}
+
+/// Works back from the stacks after all operations to the ones before them.
+/// Crates may be `UNKNOWN`, e.g. when only the tops were recorded; they stay
+/// unknown but still take up their place.
+///
+/// Fails on the last operation (in log order) that cannot have happened,
+/// such as one moving more crates than its target stack ends up with.
+fn unwind(
+    crane: &dyn Crane,
+    operations: &[Operation],
+    final_stacks: &[Stack],
+) -> Result<Vec<Stack>, CraneError> {
+    let mut stacks = final_stacks.to_vec();
+    for (i, op) in operations.iter().enumerate().rev() {
+        let step = i + 1;
+        for stack in [op.from, op.to] {
+            if stack == 0 || stack > stacks.len() {
+                return Err(CraneError::NoSuchStack(step, op.clone(), stack));
+            }
+        }
+        let available = stacks[op.to - 1].len();
+        if available < op.count {
+            return Err(CraneError::NotEnoughCrates(step, op.clone(), available));
+        }
+        crane
+            .check(op)
+            .map_err(|reason| CraneError::Refused(step, op.clone(), reason))?;
+
+        if op.from == op.to {
+            continue;
+        }
+
+        let placed = stacks[op.to - 1].split_off(available - op.count);
+        let lifted = unplace(crane, placed);
+        stacks[op.from - 1].extend(lifted);
+    }
+    return Ok(stacks);
+}
+
+/// Fewest crates each stack can end up with when every operation had enough
+/// crates to lift and every stack still has a top at the end.
+///
+/// Heights change by the same amounts whatever the stacks start with, so the
+/// fewest starting crates are the largest shortfall seen along the way.
+fn final_heights(stacks: usize, operations: &[Operation]) -> Result<Vec<usize>, CraneError> {
+    // change since the start and crates needed at the start, per stack
+    let mut change = vec![0i64; stacks];
+    let mut needed = vec![0i64; stacks];
+    for (i, op) in operations.iter().enumerate() {
+        for stack in [op.from, op.to] {
+            if stack == 0 || stack > stacks {
+                return Err(CraneError::NoSuchStack(i + 1, op.clone(), stack));
+            }
+        }
+        let from = op.from - 1;
+        needed[from] = needed[from].max(op.count as i64 - change[from]);
+        change[from] -= op.count as i64;
+        change[op.to - 1] += op.count as i64;
+    }
+
+    return Ok((0..stacks)
+        .map(|s| (needed[s].max(1 - change[s]) + change[s]) as usize)
+        .collect());
+}
+
+/// Final stacks with only their tops known, as low as the operations allow.
+fn from_tops(tops: &[Crate], operations: &[Operation]) -> Result<Vec<Stack>, CraneError> {
+    let heights = final_heights(tops.len(), operations)?;
+    return Ok(tops
+        .iter()
+        .zip(heights)
+        .map(|(top, height)| {
+            let mut stack = vec![UNKNOWN.to_string(); height - 1];
+            stack.push(top.clone());
+            stack
+        })
+        .collect());
+}
+
+fn rearrange(crane: &dyn Crane, input: &Input) -> Vec<Stack> {
+    match simulate(crane, input) {
+        Ok(stacks) => stacks,
+        Err(e) => panic!("Bad instruction: {:?}", e),
+    }
+}
+
+fn rearrange_one_at_a_time(input: &Input) -> Vec<Stack> {
+    return rearrange(&CrateMover9000, input);
+}
+
+fn rearrange_all_at_once(input: &Input) -> Vec<Stack> {
+    return rearrange(&CrateMover9001, input);
+}
+
+// Draws the stacks the same way the puzzle input does, labels included.
+// Columns widen to fit longer crates and labels, which `parse_stacks`
+// reads back since labels stay right after the start of their column.
+fn render(stacks: &Vec<Stack>) -> String {
+    let height = stacks.iter().map(|s| s.len()).max().unwrap_or(0);
+    let widths: Vec<usize> = stacks
+        .iter()
+        .enumerate()
+        .map(|(i, s)| {
+            let label = (i + 1).to_string().len();
+            let widest = s.iter().map(|c| c.chars().count()).max().unwrap_or(1);
+            label.max(widest) + 2
+        })
+        .collect();
+
+    let mut lines = Vec::new();
+    for level in (0..height).rev() {
+        let row: Vec<String> = stacks
+            .iter()
+            .zip(&widths)
+            .map(|(s, width)| match s.get(level) {
+                Some(c) => format!("{:<1$}", format!("[{}]", c), width),
+                None => " ".repeat(*width),
+            })
+            .collect();
+        lines.push(row.join(" "));
     }
+    let labels: Vec<String> = widths
+        .iter()
+        .enumerate()
+        .map(|(i, width)| format!(" {:<1$}", i + 1, width - 1))
+        .collect();
+    lines.push(labels.join(" "));
+
+    return lines.join("\n") + "\n";
+}
+
+/// Top crate of every stack, a space for a stack that ended up empty.
+fn tops(stacks: &Vec<Stack>) -> String {
+    return stacks
+        .iter()
+        .map(|s| s.last().map_or(" ", |c| c.as_str()))
+        .collect::<String>();
+}
 
-    return stacks.iter().map(|s| s.last().unwrap()).collect::<String>();
+fn first(input: &Input) -> String {
+    return tops(&rearrange_one_at_a_time(input));
+}
+
+fn second(input: &Input) -> String {
+    return tops(&rearrange_all_at_once(input));
+}
+
+fn crane_from(name: &str) -> Box<dyn Crane> {
+    match name {
+        "9000" => Box::new(CrateMover9000),
+        "9001" => Box::new(CrateMover9001),
+        capacity => Box::new(LimitedLift {
+            capacity: capacity
+                .parse()
+                .expect("expected 9000, 9001 or a lift capacity"),
+        }),
+    }
+}
+
+fn crane_label(name: &str) -> String {
+    match name {
+        "9000" | "9001" => format!("CrateMover {}", name),
+        capacity => format!("capacity {}", capacity),
+    }
 }
 
 fn main() {
-    let parsed = parse(&lines());
-    println!("first = {}", first(&parsed));
-    println!("second = {}", second(&parsed));
+    let args: Vec<String> = env::args().skip(1).collect();
+    let parsed = measured("parse", || cached("day05", &lines(), parse));
+
+    // `day05 replay [crane]` prints the stacks after every operation
+    if args.first().map(|a| a.as_str()) == Some("replay") {
+        let crane = crane_from(args.get(1).map(|a| a.as_str()).unwrap_or("9000"));
+        let (output, error) = replay(crane.as_ref(), &parsed);
+        print!("{}", output);
+        if let Some(e) = error {
+            eprintln!("error: {:?}", e);
+            process::exit(1);
+        }
+        return report_total();
+    }
+
+    // `day05 reverse [crane]` reads the drawing as the state after all
+    // operations and prints the one before them; with `--tops <tops>` only
+    // the final tops are known (one character per crate, or comma separated)
+    if args.first().map(|a| a.as_str()) == Some("reverse") {
+        let args: Vec<&str> = args[1..].iter().map(|a| a.as_str()).collect();
+        let (tops, crane) = match args.as_slice() {
+            ["--tops", tops, crane @ ..] => (Some(*tops), crane.first().copied()),
+            crane => (None, crane.first().copied()),
+        };
+        let crane = crane_from(crane.unwrap_or("9000"));
+        let final_stacks = match tops {
+            None => Ok(parsed.stacks.clone()),
+            Some(tops) => {
+                let tops: Vec<Crate> = match tops.contains(',') {
+                    true => tops.split(',').map(|t| t.to_string()).collect(),
+                    false => tops.chars().map(|t| t.to_string()).collect(),
+                };
+                // the label row says how many stacks there are
+                if tops.len() != parsed.stacks.len() {
+                    eprintln!(
+                        "error: {} tops for {} stacks",
+                        tops.len(),
+                        parsed.stacks.len()
+                    );
+                    process::exit(1);
+                }
+                from_tops(&tops, &parsed.operations)
+            }
+        };
+        match final_stacks.and_then(|s| unwind(crane.as_ref(), &parsed.operations, &s)) {
+            Ok(stacks) => {
+                print!("{}", render(&stacks));
+                let unknown = stacks.iter().flatten().filter(|c| *c == UNKNOWN).count();
+                if unknown > 0 {
+                    eprintln!("{} crates could not be determined", unknown);
+                }
+                if tops.is_some() {
+                    eprintln!("stacks are as low as the operations allow");
+                }
+            }
+            Err(e) => {
+                eprintln!("impossible: {:?}", e);
+                process::exit(1);
+            }
+        }
+        return report_total();
+    }
+
+    println!("first = {}", measured("first", || first(&parsed)));
+    println!("second = {}", measured("second", || second(&parsed)));
+    // `day05 <crane>` also runs the CrateMover 9000 or 9001, or a crane
+    // that lifts at most n crates at once
+    if let Some(name) = args.first() {
+        let crane = crane_from(name);
+        println!(
+            "{} = {}",
+            crane_label(name),
+            tops(&rearrange(crane.as_ref(), &parsed))
+        );
+    }
+    report_total();
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use aoc_2022_rust::aoc::snapshot::assert_snapshot;
     const INPUT: &str = "    [D]    
 [N] [C]    
 [Z] [M] [P]
-    1   2   3 
+ 1   2   3 
 
 move 1 from 2 to 1
 move 3 from 1 to 3
@@ -131,4 +579,294 @@ move 1 from 1 to 2";
         assert_eq!("MCD", second(&input));
     }
 
+    fn crates(names: &str) -> Stack {
+        return names.chars().map(|c| c.to_string()).collect();
+    }
+
+    #[test]
+    fn test_cranes() {
+        let input = parse(&INPUT.lines().map(|l| l.to_string()).collect());
+        let one = LimitedLift { capacity: 1 };
+        let all = LimitedLift { capacity: 3 };
+        assert_eq!(rearrange_one_at_a_time(&input), rearrange(&one, &input));
+        assert_eq!(rearrange_all_at_once(&input), rearrange(&all, &input));
+
+        let two = LimitedLift { capacity: 2 };
+        assert_eq!(crates("debca"), two.place(crates("abcde")));
+        assert_eq!("MCZ", tops(&rearrange(&two, &input)));
+    }
+
+    struct SmallCrane;
+
+    impl Crane for SmallCrane {
+        fn place(&self, lifted: Stack) -> Stack {
+            return lifted;
+        }
+
+        fn check(&self, op: &Operation) -> Result<(), String> {
+            if op.count > 2 {
+                return Err(format!("cannot lift {} crates", op.count));
+            }
+            return Ok(());
+        }
+    }
+
+    #[test]
+    fn test_invalid_operations() {
+        let mut input = parse(&INPUT.lines().map(|l| l.to_string()).collect());
+        let op = |count, from, to| Operation { from, to, count };
+        assert_eq!(
+            Err(CraneError::Refused(
+                2,
+                op(3, 1, 3),
+                "cannot lift 3 crates".to_string()
+            )),
+            simulate(&SmallCrane, &input)
+        );
+
+        input.operations[1] = op(4, 1, 3);
+        assert_eq!(
+            Err(CraneError::NotEnoughCrates(2, op(4, 1, 3), 3)),
+            simulate(&CrateMover9000, &input)
+        );
+
+        input.operations[1] = op(1, 1, 4);
+        assert_eq!(
+            Err(CraneError::NoSuchStack(2, op(1, 1, 4), 4)),
+            simulate(&CrateMover9001, &input)
+        );
+
+        // legal, but leaves a stack without a top
+        let emptied = parse(&to_lines("[A] [B]\n 1   2 \n\nmove 1 from 2 to 1"));
+        assert_eq!("B ", first(&emptied));
+        assert_eq!("B ", second(&emptied));
+    }
+
+    #[test]
+    fn test_from_tops() {
+        let input = parse(&INPUT.lines().map(|l| l.to_string()).collect());
+        // the third stack may well have started empty
+        assert_eq!(Ok(vec![1, 1, 3]), final_heights(3, &input.operations));
+
+        let final_stacks = from_tops(&crates("CMZ"), &input.operations).unwrap();
+        let stacks = unwind(&CrateMover9000, &input.operations, &final_stacks).unwrap();
+        assert_eq!(
+            vec![2, 3, 0],
+            stacks.iter().map(|s| s.len()).collect::<Vec<usize>>()
+        );
+        let replayed = Input {
+            stacks,
+            operations: input.operations.clone(),
+        };
+        assert_eq!("CMZ", first(&replayed));
+
+        assert!(matches!(
+            final_heights(2, &input.operations),
+            Err(CraneError::NoSuchStack(2, _, 3))
+        ));
+    }
+
+    #[test]
+    fn test_same_stack() {
+        let input = Input {
+            stacks: vec![crates("CBA")],
+            operations: vec![Operation {
+                from: 1,
+                to: 1,
+                count: 2,
+            }],
+        };
+        let cranes: [&dyn Crane; 3] = [
+            &CrateMover9000,
+            &CrateMover9001,
+            &LimitedLift { capacity: 1 },
+        ];
+        for crane in cranes {
+            assert_eq!(Ok(vec![crates("CBA")]), simulate(crane, &input));
+            assert_eq!(
+                Ok(vec![crates("CBA")]),
+                unwind(crane, &input.operations, &input.stacks)
+            );
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let input = parse(&INPUT.lines().map(|l| l.to_string()).collect());
+        assert_snapshot(
+            "day05_one_at_a_time",
+            &render(&rearrange_one_at_a_time(&input)),
+        );
+        assert_snapshot("day05_all_at_once", &render(&rearrange_all_at_once(&input)));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let drawing = "        [Q]\n    [D] [W]\n[N] [C] [E]\n[Z] [M] [P]\n 1   2   3 \n";
+        let mut lines: Vec<String> = drawing.lines().map(|l| l.to_string()).collect();
+        lines.push(String::new());
+        assert_eq!(drawing, render(&parse(&lines).stacks));
+
+        // and back again, for every intermediate state
+        lines.extend(INPUT.lines().skip(5).map(|l| l.to_string()));
+        let input = parse(&lines);
+        assert_eq!(4, input.operations.len());
+        for step in Replay::new(&CrateMover9001, &input) {
+            let (_, stacks) = step.unwrap();
+            let mut lines: Vec<String> = render(&stacks).lines().map(|l| l.to_string()).collect();
+            lines.push(String::new());
+            assert_eq!(stacks, parse(&lines).stacks);
+        }
+    }
+
+    #[test]
+    fn test_replay() {
+        let mut input = parse(&INPUT.lines().map(|l| l.to_string()).collect());
+        let (output, error) = replay(&CrateMover9000, &input);
+        assert_eq!(None, error);
+        assert_snapshot("day05_replay", &output);
+
+        input.operations[2].count = 5;
+        let (output, error) = replay(&CrateMover9000, &input);
+        assert_eq!(3, output.matches("move").count() + 1);
+        assert!(matches!(error, Some(CraneError::NotEnoughCrates(3, _, 2))));
+    }
+
+    fn to_lines(s: &str) -> Vec<String> {
+        return s.lines().map(|l| l.to_string()).collect();
+    }
+
+    #[test]
+    fn test_irregular_layouts() {
+        // trailing whitespace stripped, more than nine stacks, long crates
+        let drawing = "[A]                                     [K]
+[B] [C]     [D] [E] [F] [G] [H] [I] [J] [L]  [M]
+ 1   2   3   4   5   6   7   8   9   10  11   12
+
+move 1 from 11 to 3";
+        let input = parse(&to_lines(drawing));
+        assert_eq!(12, input.stacks.len());
+        assert_eq!(crates("BA"), input.stacks[0]);
+        assert!(input.stacks[2].is_empty());
+        assert_eq!(crates("LK"), input.stacks[10]);
+        assert_eq!(
+            vec![Operation {
+                from: 11,
+                to: 3,
+                count: 1
+            }],
+            input.operations
+        );
+
+        let stacks = vec![
+            vec!["AB".to_string(), "CDE".to_string()],
+            vec![],
+            crates("XYZ"),
+            crates("Q"),
+            crates("R"),
+            crates("S"),
+            crates("T"),
+            crates("U"),
+            crates("V"),
+            vec!["LONG".to_string()],
+        ];
+        let mut lines = to_lines(&render(&stacks));
+        lines.push(String::new());
+        assert_eq!(stacks, parse(&lines).stacks);
+        let stripped: Vec<String> = lines.iter().map(|l| l.trim_end().to_string()).collect();
+        assert_eq!(stacks, parse(&stripped).stacks);
+    }
+
+    #[test]
+    fn test_layout_errors() {
+        let error = |drawing: &str| parse_input(&to_lines(drawing)).err();
+        assert_eq!(
+            Some(LayoutError::MissingLabels),
+            error("[A]\n\nmove 1 from 1 to 1")
+        );
+        assert_eq!(
+            Some(LayoutError::BadLabels(
+                2,
+                "expected stack 2, found '3'".to_string()
+            )),
+            error("[A] [B]\n 1   3 \n\n")
+        );
+        assert_eq!(
+            Some(LayoutError::FloatingCrate(1, "A".to_string())),
+            error("[A]\n    [B]\n 1   2 \n\n")
+        );
+        assert_eq!(
+            Some(LayoutError::UnalignedCrate(1, "B".to_string())),
+            error("[A]   [B]\n 1   2 \n\n")
+        );
+        assert_eq!(
+            Some(LayoutError::MalformedRow(1, "A]".to_string())),
+            error("[B] A]\n 1   2 \n\n")
+        );
+        assert_eq!(
+            Some(LayoutError::BadOperation(
+                4,
+                "move one from 1 to 2".to_string()
+            )),
+            error("[A]\n 1   2 \n\nmove one from 1 to 2")
+        );
+    }
+
+    #[test]
+    fn test_unwind() {
+        let input = parse(&to_lines(INPUT));
+        let cranes: [&dyn Crane; 3] = [
+            &CrateMover9000,
+            &CrateMover9001,
+            &LimitedLift { capacity: 2 },
+        ];
+        for crane in cranes {
+            let final_stacks = rearrange(crane, &input);
+            assert_eq!(
+                Ok(input.stacks.clone()),
+                unwind(crane, &input.operations, &final_stacks)
+            );
+        }
+
+        // only the tops are known, which pins down the initial Z, M and C
+        let unknown = || UNKNOWN.to_string();
+        let recorded = vec![
+            vec!["C".to_string()],
+            vec!["M".to_string()],
+            vec![unknown(), unknown(), unknown(), "Z".to_string()],
+        ];
+        let initial = unwind(&CrateMover9000, &input.operations, &recorded).unwrap();
+        assert_eq!(
+            vec![
+                vec!["Z".to_string(), unknown()],
+                vec!["M".to_string(), "C".to_string(), unknown()],
+                vec![unknown()],
+            ],
+            initial
+        );
+        let replayed = Input {
+            stacks: initial,
+            operations: input.operations.clone(),
+        };
+        assert_eq!(recorded, rearrange(&CrateMover9000, &replayed));
+    }
+
+    #[test]
+    fn test_unwind_impossible() {
+        let input = parse(&to_lines(INPUT));
+        // stack 3 cannot have received three crates if it ends up with two
+        let final_stacks = vec![crates("C"), crates("M"), crates("PD")];
+        assert_eq!(
+            Err(CraneError::NotEnoughCrates(
+                2,
+                Operation {
+                    from: 1,
+                    to: 3,
+                    count: 3
+                },
+                2
+            )),
+            unwind(&CrateMover9000, &input.operations, &final_stacks)
+        );
+    }
 }
@@ -1,29 +1,306 @@
-use std::collections::HashSet;
+use std::{
+    collections::VecDeque,
+    env,
+    io::{self, BufReader, Bytes, Read},
+    process,
+};
 
-use aoc_2022_rust::aoc::input::as_str;
+use aoc_2022_rust::aoc::{
+    input::as_str,
+    memory::{measured, report_total},
+};
 
-fn find_unique_n(input: &str, n: usize) -> Option<usize> {
-    input
-        .char_indices()
-        .map(move |(i, _)| (i, input.chars().skip(i).take(n).collect::<HashSet<char>>()))
-        .filter(|(_, slice)| slice.len() == n)
-        .take(1)
-        .last()
-        .map(|(i, _)| i + n)
+/// Sliding window over a signal that knows in O(1) per character whether
+/// its last `size` characters are all different.
+struct MarkerDetector {
+    size: usize,
+    window: VecDeque<u8>,
+    counts: [usize; 256],
+    // how many characters of the window are repeats of an earlier one
+    repeated: usize,
+    // characters seen so far
+    position: usize,
+}
+
+impl MarkerDetector {
+    fn new(size: usize) -> MarkerDetector {
+        assert!(size > 0, "markers need at least one character");
+        MarkerDetector {
+            size,
+            window: VecDeque::with_capacity(size + 1),
+            counts: [0; 256],
+            repeated: 0,
+            position: 0,
+        }
+    }
+
+    /// Adds a character and returns whether the window now is a marker.
+    fn push(&mut self, c: u8) -> bool {
+        if self.counts[c as usize] > 0 {
+            self.repeated += 1;
+        }
+        self.counts[c as usize] += 1;
+        self.window.push_back(c);
+        self.position += 1;
+
+        if self.window.len() > self.size {
+            let old = self.window.pop_front().unwrap() as usize;
+            self.counts[old] -= 1;
+            if self.counts[old] > 0 {
+                self.repeated -= 1;
+            }
+        }
+
+        return self.window.len() == self.size && self.repeated == 0;
+    }
+}
+
+/// Positions right after every marker of a signal, read lazily.
+///
+/// Line breaks are not part of the signal, so a trailing newline (or a log
+/// split over several lines) does not shift positions or break a marker.
+struct Markers<R: Read> {
+    bytes: Bytes<BufReader<R>>,
+    detector: MarkerDetector,
+}
+
+impl<R: Read> Iterator for Markers<R> {
+    type Item = io::Result<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for byte in &mut self.bytes {
+            let c = match byte {
+                Ok(b'\n') | Ok(b'\r') => continue,
+                Ok(c) => c,
+                Err(e) => return Some(Err(e)),
+            };
+            if self.detector.push(c) {
+                return Some(Ok(self.detector.position));
+            }
+        }
+        return None;
+    }
+}
+
+fn markers<R: Read>(reader: R, size: usize) -> Markers<R> {
+    Markers {
+        bytes: BufReader::new(reader).bytes(),
+        detector: MarkerDetector::new(size),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum FrameKind {
+    Packet,
+    Message,
+}
+
+#[derive(Debug, PartialEq)]
+enum Violation {
+    // a packet marker was not followed by a message marker
+    MissingMessage,
+    // a message marker directly followed by the next packet marker
+    EmptyMessage,
+}
+
+/// Part of a signal opened by a marker. Offsets count signal characters,
+/// like marker positions.
+#[derive(Debug, PartialEq)]
+struct Frame {
+    kind: FrameKind,
+    // where the opening marker starts and where the payload starts
+    offset: usize,
+    payload_offset: usize,
+    payload: String,
+    violation: Option<Violation>,
+}
+
+/// Marker sizes of a protocol where a packet marker opens a packet header,
+/// which runs until a message marker opens the message, which runs until
+/// the next packet marker.
+///
+/// Only the first packet marker has the packet size. Later ones end a
+/// message, so they are as long as message markers to keep ordinary message
+/// data from ending it. A message marker may overlap the packet marker
+/// before it, as in the puzzle; every other marker is searched for from the
+/// end of the previous one.
+#[derive(Clone, Copy)]
+struct Protocol {
+    packet: usize,
+    message: usize,
+}
+
+const DEVICE: Protocol = Protocol {
+    packet: 4,
+    message: 14,
+};
+
+/// Splits a signal into frames, read lazily. Anything before the first
+/// packet marker is noise and not reported.
+struct Frames<R: Read> {
+    bytes: Bytes<BufReader<R>>,
+    protocol: Protocol,
+    // the frame being read, without its payload
+    current: Option<(FrameKind, usize, usize)>,
+    payload: Vec<u8>,
+    detector: MarkerDetector,
+    position: usize,
+    done: bool,
+}
+
+impl<R: Read> Frames<R> {
+    fn finish(&mut self, payload: Vec<u8>, at_end: bool) -> Option<Frame> {
+        let (kind, offset, payload_offset) = self.current?;
+        let violation = match kind {
+            FrameKind::Packet if at_end => Some(Violation::MissingMessage),
+            FrameKind::Message if payload.is_empty() => Some(Violation::EmptyMessage),
+            _ => None,
+        };
+        return Some(Frame {
+            kind,
+            offset,
+            payload_offset,
+            payload: String::from_utf8_lossy(&payload).to_string(),
+            violation,
+        });
+    }
+}
+
+impl<R: Read> Iterator for Frames<R> {
+    type Item = io::Result<Frame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            let c = match self.bytes.next() {
+                Some(Ok(b'\n')) | Some(Ok(b'\r')) => continue,
+                Some(Ok(c)) => c,
+                Some(Err(e)) => return Some(Err(e)),
+                None => {
+                    self.done = true;
+                    let payload = std::mem::take(&mut self.payload);
+                    return self.finish(payload, true).map(Ok);
+                }
+            };
+
+            self.position += 1;
+            self.payload.push(c);
+            if !self.detector.push(c) {
+                continue;
+            }
+
+            // the marker ends the current frame and opens the next one; a
+            // header overlapped by its message marker is empty
+            let size = self.detector.size;
+            let mut payload = std::mem::take(&mut self.payload);
+            let first_packet = self.current.is_none();
+            let mut detector = MarkerDetector::new(self.protocol.message);
+            if first_packet {
+                let seen = payload.len().saturating_sub(self.protocol.message - 1);
+                for c in &payload[seen..] {
+                    detector.push(*c);
+                }
+            }
+            payload.truncate(payload.len().saturating_sub(size));
+            let finished = self.finish(payload, false);
+
+            let next = match self.current {
+                Some((FrameKind::Packet, _, _)) => FrameKind::Message,
+                _ => FrameKind::Packet,
+            };
+            self.current = Some((next, self.position - size, self.position));
+            self.detector = detector;
+
+            if let Some(frame) = finished {
+                return Some(Ok(frame));
+            }
+        }
+        return None;
+    }
+}
+
+fn frames<R: Read>(reader: R, protocol: Protocol) -> Frames<R> {
+    Frames {
+        bytes: BufReader::new(reader).bytes(),
+        protocol,
+        current: None,
+        payload: Vec::new(),
+        detector: MarkerDetector::new(protocol.packet),
+        position: 0,
+        done: false,
+    }
+}
+
+fn find_marker(input: &str, size: usize) -> Option<usize> {
+    markers(input.as_bytes(), size).next().map(|m| m.unwrap())
 }
 
 fn first(input: &str) -> usize {
-    find_unique_n(input, 4).unwrap()
+    find_marker(input, 4).unwrap()
 }
 
 fn second(input: &str) -> usize {
-    find_unique_n(input, 14).unwrap()
+    find_marker(input, 14).unwrap()
+}
+
+/// A marker size from the command line, which has to be at least 1.
+fn marker_size(arg: &str, usage: &str) -> usize {
+    match arg.parse() {
+        Ok(size) if size > 0 => size,
+        _ => {
+            eprintln!("error: '{}' is not a marker size\nusage: {}", arg, usage);
+            process::exit(1);
+        }
+    }
 }
 
+const FRAMES_USAGE: &str = "day06 frames [<packet> <message>]";
+
 fn main() {
-    let parsed = as_str();
-    println!("first = {}", first(&parsed));
-    println!("second = {}", second(&parsed));
+    // `day06 all <size>` streams stdin and prints every marker position,
+    // `day06 frames [<packet> <message>]` prints the frames of the signal
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args
+        .iter()
+        .map(|a| a.as_str())
+        .collect::<Vec<&str>>()
+        .as_slice()
+    {
+        ["all", size] => {
+            let size = marker_size(size, "day06 all <size>");
+            for marker in markers(io::stdin().lock(), size) {
+                println!("{}", marker.expect("cannot read signal"));
+            }
+            return report_total();
+        }
+        ["frames", sizes @ ..] => {
+            let protocol = match sizes {
+                [] => DEVICE,
+                [packet, message] => Protocol {
+                    packet: marker_size(packet, FRAMES_USAGE),
+                    message: marker_size(message, FRAMES_USAGE),
+                },
+                _ => panic!("usage: {}", FRAMES_USAGE),
+            };
+            for frame in frames(io::stdin().lock(), protocol) {
+                let frame = frame.expect("cannot read signal");
+                let violation = match &frame.violation {
+                    Some(v) => format!(" ({:?})", v),
+                    None => String::new(),
+                };
+                println!(
+                    "{:?} at {}: {}{}",
+                    frame.kind, frame.payload_offset, frame.payload, violation
+                );
+            }
+            return report_total();
+        }
+        _ => (),
+    }
+
+    let parsed = measured("parse", as_str);
+    println!("first = {}", measured("first", || first(&parsed)));
+    println!("second = {}", measured("second", || second(&parsed)));
+    report_total();
 }
 
 #[cfg(test)]
@@ -46,4 +323,114 @@ mod tests {
         assert_eq!(29, second("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg"));
         assert_eq!(26, second("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw"));
     }
+
+    fn all(input: &str, size: usize) -> Vec<usize> {
+        markers(input.as_bytes(), size)
+            .map(|m| m.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_markers() {
+        assert_eq!(vec![3, 4, 5, 8], all("abcabbca", 3));
+        assert_eq!(vec![1, 2, 3], all("aaa", 1));
+        assert_eq!(Vec::<usize>::new(), all("abc", 4));
+
+        // the newline `as_str` keeps neither counts nor ends a window
+        assert_eq!(vec![4], all("abcd\n", 4));
+        assert_eq!(vec![4, 5], all("ab\r\ncde", 4));
+        assert_eq!(
+            Some(26),
+            find_marker("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw\n", 14)
+        );
+    }
+
+    fn decode(signal: &str, protocol: Protocol) -> Vec<Frame> {
+        frames(signal.as_bytes(), protocol)
+            .map(|f| f.unwrap())
+            .collect()
+    }
+
+    fn frame(
+        kind: FrameKind,
+        offset: usize,
+        payload_offset: usize,
+        payload: &str,
+        violation: Option<Violation>,
+    ) -> Frame {
+        Frame {
+            kind,
+            offset,
+            payload_offset,
+            payload: payload.to_string(),
+            violation,
+        }
+    }
+
+    #[test]
+    fn test_frames() {
+        use FrameKind::*;
+        let protocol = Protocol {
+            packet: 2,
+            message: 3,
+        };
+        // `aa` is noise, `ab` the first packet marker and the overlapping
+        // `bxy` a message marker; `cad` ends the message with a new packet
+        assert_eq!(
+            vec![
+                frame(Packet, 2, 4, "", None),
+                frame(Message, 4, 7, "zcc", None),
+                frame(Packet, 10, 13, "", None),
+                frame(Message, 13, 16, "cqq", None),
+            ],
+            decode("aaab\nbxyzcccaddabcqq", protocol)
+        );
+        assert_eq!(
+            vec![frame(Packet, 0, 2, "ab", Some(Violation::MissingMessage))],
+            decode("abab", protocol)
+        );
+        assert_eq!(
+            vec![
+                frame(Packet, 0, 2, "", None),
+                frame(Message, 0, 3, "", Some(Violation::EmptyMessage)),
+                frame(Packet, 3, 6, "", Some(Violation::MissingMessage)),
+            ],
+            decode("abcdef", protocol)
+        );
+    }
+
+    #[test]
+    fn test_device_frames() {
+        // the packet starts where part one finds its marker and the message
+        // where part two does, carrying the rest of the signal
+        for signal in [
+            "mjqjpqmgbljsphdztnvjfqwrcgsmlb",
+            "bvwbjplbgvbhsrlpgdmjqwftvncz",
+            "nppdvjthqldpwncqszvftbrmjlhg",
+            "nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg",
+            "zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw",
+        ] {
+            let decoded = decode(signal, DEVICE);
+            assert_eq!(2, decoded.len());
+            assert_eq!(
+                (FrameKind::Packet, first(signal)),
+                (decoded[0].kind, decoded[0].payload_offset)
+            );
+            assert_eq!(
+                (FrameKind::Message, second(signal)),
+                (decoded[1].kind, decoded[1].payload_offset)
+            );
+            assert_eq!(&signal[second(signal)..], decoded[1].payload);
+            assert_eq!(None, decoded[1].violation);
+        }
+
+        // a message only ends at a marker as long as a message marker
+        let signal = "abcdefghijklmnaabbaacdcdabcdefghijklmnop";
+        let decoded = decode(signal, DEVICE);
+        assert_eq!("aabbaacdcd", decoded[1].payload);
+        assert_eq!(
+            (FrameKind::Packet, 38),
+            (decoded[2].kind, decoded[2].payload_offset)
+        );
+    }
 }
@@ -1,98 +1,533 @@
-use std::{cmp::min, collections::HashMap, slice::Iter};
+use std::{collections::BTreeMap, env};
 
-use aoc_2022_rust::aoc::input::lines;
+use aoc_2022_rust::aoc::{
+    cache::cached,
+    input::{file_lines, lines},
+    memory::{measured, report_total},
+    repl::{self, Repl},
+};
+use serde::{Deserialize, Serialize};
 
-struct File {
-    size: usize,
-}
+/// Index of a directory in `FileSystem::dirs`.
+type DirId = usize;
 
+#[derive(Deserialize, Serialize)]
 struct Directory {
-    dirs: HashMap<String, Directory>,
-    // files: HashMap<String, File>, // this is not needed for the solution
+    name: String,
+    parent: Option<DirId>,
+    dirs: BTreeMap<String, DirId>,
+    // file name -> size
+    files: BTreeMap<String, usize>,
+    // whether the transcript ran `ls` in here
+    listed: bool,
+    // everything below, filled in by `FileSystem::update_sizes`
     size: usize,
+    // false when the size is only a lower bound, because this directory or
+    // one below it was never listed
+    complete: bool,
 }
 
-fn ls_dir(iter: &mut Iter<String>) -> Directory {
-    let mut dirs = HashMap::new();
-    let mut files = HashMap::new();
+/// Directory tree as seen through a terminal transcript. Directories refer
+/// to each other by index, children always after their parent.
+#[derive(Deserialize, Serialize)]
+struct FileSystem {
+    dirs: Vec<Directory>,
+}
+
+const ROOT: DirId = 0;
+
+impl FileSystem {
+    fn new() -> FileSystem {
+        return FileSystem {
+            dirs: vec![Directory {
+                name: "/".to_string(),
+                parent: None,
+                dirs: BTreeMap::new(),
+                files: BTreeMap::new(),
+                listed: false,
+                size: 0,
+                complete: false,
+            }],
+        };
+    }
+
+    fn dir(&self, id: DirId) -> &Directory {
+        return &self.dirs[id];
+    }
+
+    /// The subdirectory `name` of `parent`, created if not seen before.
+    fn mkdir(&mut self, parent: DirId, name: &str) -> DirId {
+        if let Some(id) = self.dirs[parent].dirs.get(name) {
+            return *id;
+        }
+
+        let id = self.dirs.len();
+        self.dirs.push(Directory {
+            name: name.to_string(),
+            parent: Some(parent),
+            dirs: BTreeMap::new(),
+            files: BTreeMap::new(),
+            listed: false,
+            size: 0,
+            complete: false,
+        });
+        self.dirs[parent].dirs.insert(name.to_string(), id);
+        return id;
+    }
+
+    /// Records a file, returning the size it was listed with before if that
+    /// was different.
+    fn add_file(&mut self, dir: DirId, name: &str, size: usize) -> Option<usize> {
+        let previous = self.dirs[dir].files.insert(name.to_string(), size);
+        return previous.filter(|p| *p != size);
+    }
+
+    fn update_sizes(&mut self) {
+        // children come after their parents, so going backwards every
+        // directory is complete before it is added to its parent
+        for id in (0..self.dirs.len()).rev() {
+            let dir = &self.dirs[id];
+            let size = dir.files.values().sum::<usize>()
+                + dir.dirs.values().map(|d| self.dirs[*d].size).sum::<usize>();
+            let complete = dir.listed && dir.dirs.values().all(|d| self.dirs[*d].complete);
+            self.dirs[id].size = size;
+            self.dirs[id].complete = complete;
+        }
+    }
+
+    fn path(&self, id: DirId) -> String {
+        let mut names = Vec::new();
+        let mut current = id;
+        while let Some(parent) = self.dirs[current].parent {
+            names.push(self.dirs[current].name.as_str());
+            current = parent;
+        }
+        names.reverse();
+        return format!("/{}", names.join("/"));
+    }
+
+    /// Resolves an absolute path, `..` included.
+    fn lookup(&self, path: &str) -> Option<DirId> {
+        if !path.starts_with('/') {
+            return None;
+        }
+
+        let mut id = ROOT;
+        for name in components(path) {
+            id = match name {
+                ".." => self.dirs[id].parent.unwrap_or(ROOT),
+                _ => *self.dirs[id].dirs.get(name)?,
+            };
+        }
+        return Some(id);
+    }
+}
+
+/// The names along a path, without the empty and `.` parts.
+fn components(path: &str) -> impl Iterator<Item = &str> {
+    return path.split('/').filter(|n| !n.is_empty() && *n != ".");
+}
+
+/// Something in a transcript that a real terminal session cannot produce,
+/// with its line number (1-based).
+#[derive(Debug, PartialEq)]
+enum Issue {
+    Unparseable(usize),
+    // `cd` into a directory no `ls` of its parent showed
+    UnlistedDirectory(usize, String),
+    // path, size listed before, size listed now
+    ConflictingSize(usize, String, usize, usize),
+    AboveRoot(usize),
+    // `ls` output without an `ls`
+    OutsideListing(usize),
+}
+
+/// Builds the filesystem, noting every inconsistency instead of giving up.
+fn read_transcript(input: &Vec<String>) -> (FileSystem, Vec<Issue>) {
+    let mut fs = FileSystem::new();
+    let mut issues = Vec::new();
+    let mut cwd = ROOT;
+    let mut listing = false;
+    // directories that appeared as `dir` in a listing
+    let mut announced = vec![true];
+
+    for (i, line) in input.iter().enumerate() {
+        let number = i + 1;
+        // names may contain spaces, so only the first one separates
+        let parts = line.split_once(' ');
+        if line.starts_with("$ ") {
+            listing = false;
+        } else if !listing {
+            issues.push(Issue::OutsideListing(number));
+        }
+
+        match parts {
+            Some(("$", "ls")) => {
+                listing = true;
+                fs.dirs[cwd].listed = true;
+            }
+            Some(("$", command)) if command.starts_with("cd ") => {
+                let target = &command[3..];
+                if target.starts_with('/') {
+                    cwd = ROOT;
+                }
+                for name in components(target) {
+                    if name == ".." {
+                        match fs.dir(cwd).parent {
+                            Some(parent) => cwd = parent,
+                            None => issues.push(Issue::AboveRoot(number)),
+                        }
+                        continue;
+                    }
 
-    while let Some(line) = iter.next() {
-        match &line[..4] {
-            "$ cd" => {
-                let dir_name = &line[5..];
-                if dir_name == ".." {
-                    break;
+                    cwd = fs.mkdir(cwd, name);
+                    announced.resize(fs.dirs.len(), false);
+                    if !announced[cwd] {
+                        issues.push(Issue::UnlistedDirectory(number, fs.path(cwd)));
+                        // reported once is enough
+                        announced[cwd] = true;
+                    }
                 }
-                dirs.insert(dir_name.to_string(), ls_dir(iter));
             }
-            "$ ls" => continue,
-            "dir " => continue,
-            _ => {
-                let (size, name) = line.split_once(' ').unwrap();
-                files.insert(
-                    name.to_string(),
-                    File {
-                        size: size.parse().unwrap(),
-                    },
-                );
+            Some(("dir", name)) => {
+                let id = fs.mkdir(cwd, name);
+                announced.resize(fs.dirs.len(), false);
+                announced[id] = true;
+            }
+            Some((size, name)) if size.parse::<usize>().is_ok() => {
+                let size = size.parse().unwrap();
+                if let Some(previous) = fs.add_file(cwd, name, size) {
+                    let path = file_path(&fs, cwd, name);
+                    issues.push(Issue::ConflictingSize(number, path, previous, size));
+                }
             }
+            _ => issues.push(Issue::Unparseable(number)),
         }
     }
 
-    let size = dirs.iter().map(|(_, d)| d.size).sum::<usize>()
-        + files.iter().map(|(_, f)| f.size).sum::<usize>();
-    return Directory { dirs, size };
+    fs.update_sizes();
+    return (fs, issues);
 }
 
-fn parse(input: &Vec<String>) -> Directory {
-    return ls_dir(&mut input.iter());
+fn parse(input: &Vec<String>) -> FileSystem {
+    return read_transcript(input).0;
 }
 
-fn sum_dirs_below(root: &Directory, limit: usize) -> usize {
-    let mut sum = 0;
-    if root.size <= limit {
-        sum += root.size;
+/// Lists the issues of a transcript and says whether the sizes computed
+/// from it can be trusted.
+fn validate(input: &Vec<String>) -> String {
+    let (fs, issues) = read_transcript(input);
+    let mut report: Vec<String> = issues
+        .iter()
+        .map(|issue| match issue {
+            Issue::Unparseable(line) => format!("line {}: cannot parse", line),
+            Issue::UnlistedDirectory(line, path) => {
+                format!("line {}: cd into {}, which no listing showed", line, path)
+            }
+            Issue::ConflictingSize(line, path, before, now) => {
+                format!(
+                    "line {}: {} listed with {} bytes, earlier {}",
+                    line, path, now, before
+                )
+            }
+            Issue::AboveRoot(line) => format!("line {}: cd .. from /", line),
+            Issue::OutsideListing(line) => format!("line {}: output without ls", line),
+        })
+        .collect();
+
+    // a file listed twice or a line that was skipped may be counted wrong
+    let mut uncertain = Vec::new();
+    if issues
+        .iter()
+        .any(|i| matches!(i, Issue::ConflictingSize(..)))
+    {
+        uncertain.push("conflicting listings");
+    }
+    if issues.iter().any(|i| matches!(i, Issue::Unparseable(_))) {
+        uncertain.push("unparseable lines");
     }
-    for (_, d) in &root.dirs {
-        sum += sum_dirs_below(&d, limit);
+    if !uncertain.is_empty() {
+        report.push(format!("sizes are uncertain: {}", uncertain.join(", ")));
     }
 
-    return sum;
+    let unlisted: Vec<String> = (0..fs.dirs.len())
+        .filter(|id| !fs.dir(*id).listed)
+        .map(|id| fs.path(id))
+        .collect();
+    if !unlisted.is_empty() {
+        report.push(format!(
+            "sizes are lower bounds, never listed: {}",
+            unlisted.join(", ")
+        ));
+    } else if uncertain.is_empty() {
+        report.push("sizes are complete".to_string());
+    }
+    return report.join("\n");
+}
+
+fn sum_dirs_below(fs: &FileSystem, limit: usize) -> usize {
+    return fs.dirs.iter().map(|d| d.size).filter(|s| *s <= limit).sum();
+}
+
+fn first(fs: &FileSystem) -> usize {
+    return sum_dirs_below(fs, 100000);
 }
 
-fn first(root: &Directory) -> usize {
-    return sum_dirs_below(root, 100000);
+fn find_best_candidate(fs: &FileSystem, limit_to_free: usize) -> Option<usize> {
+    return fs
+        .dirs
+        .iter()
+        .map(|d| d.size)
+        .filter(|s| *s >= limit_to_free)
+        .min();
 }
 
-fn find_best_candidate(root: &Directory, limit_to_free: usize) -> Option<usize> {
-    if root.size < limit_to_free {
-        return None;
+fn second(fs: &FileSystem) -> usize {
+    let disk_space: usize = 70000000;
+    let required_unused: usize = 30000000;
+    let currently_unused = disk_space - fs.dir(ROOT).size;
+
+    return find_best_candidate(fs, required_unused - currently_unused).unwrap();
+}
+
+/// `tree`-like listing of `id` and everything below it, with sizes.
+fn tree(fs: &FileSystem, id: DirId) -> String {
+    fn walk(fs: &FileSystem, id: DirId, depth: usize, lines: &mut Vec<String>) {
+        let dir = fs.dir(id);
+        let indent = "  ".repeat(depth);
+        lines.push(format!("{}- {} (dir, size={})", indent, dir.name, dir.size));
+
+        // directories and files mixed, by name
+        let mut entries: Vec<(&String, Option<DirId>, usize)> = Vec::new();
+        entries.extend(dir.dirs.iter().map(|(n, d)| (n, Some(*d), 0)));
+        entries.extend(dir.files.iter().map(|(n, s)| (n, None, *s)));
+        entries.sort();
+        for (name, subdir, size) in entries {
+            match subdir {
+                Some(d) => walk(fs, d, depth + 1, lines),
+                None => lines.push(format!("{}  - {} (file, size={})", indent, name, size)),
+            }
+        }
     }
 
-    let mut candidate = root.size;
+    let mut lines = Vec::new();
+    walk(fs, id, 0, &mut lines);
+    return lines.join("\n");
+}
+
+/// The `n` biggest directories by total size, biggest first.
+fn largest_dirs(fs: &FileSystem, n: usize) -> Vec<(String, usize)> {
+    let mut dirs: Vec<(String, usize)> = (0..fs.dirs.len())
+        .map(|id| (fs.path(id), fs.dir(id).size))
+        .collect();
+    dirs.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    dirs.truncate(n);
+    return dirs;
+}
+
+fn file_path(fs: &FileSystem, dir: DirId, name: &str) -> String {
+    return match dir {
+        ROOT => format!("/{}", name),
+        _ => format!("{}/{}", fs.path(dir), name),
+    };
+}
 
-    for (_, dir) in &root.dirs {
-        match find_best_candidate(dir, limit_to_free) {
-            Some(v) => candidate = min(candidate, v),
-            None => continue,
+/// Every file, by path, for which `predicate(name, size)` holds.
+fn find_files<P: Fn(&str, usize) -> bool>(fs: &FileSystem, predicate: P) -> Vec<(String, usize)> {
+    let mut found = Vec::new();
+    for id in 0..fs.dirs.len() {
+        for (name, size) in &fs.dir(id).files {
+            if predicate(name, *size) {
+                found.push((file_path(fs, id, name), *size));
+            }
         }
     }
+    found.sort();
+    return found;
+}
 
-    return Some(candidate);
+#[derive(Debug, PartialEq)]
+enum Plan {
+    Directory(String, usize),
+    Files(Vec<(String, usize)>, usize),
 }
 
-fn second(root: &Directory) -> usize {
-    let disk_space: usize = 70000000;
-    let required_unused: usize = 30000000;
-    let currently_unused = disk_space - root.size;
+/// What to delete to free at least `bytes`, deleting as little as possible:
+/// either the smallest single directory that is big enough or a set of
+/// files, whichever is smaller.
+///
+/// Picking the best set of files is a subset sum problem, so it is a
+/// heuristic: repeatedly either finish with the smallest file covering what
+/// is still missing, or take the largest file that does not and go on,
+/// keeping the cheapest finish seen.
+fn plan_deletion(fs: &FileSystem, bytes: usize) -> Option<Plan> {
+    let directory = (0..fs.dirs.len())
+        .filter(|id| fs.dir(*id).size >= bytes)
+        .min_by_key(|id| fs.dir(*id).size)
+        .map(|id| Plan::Directory(fs.path(id), fs.dir(id).size));
 
-    return find_best_candidate(root, required_unused - currently_unused).unwrap();
+    let mut available = find_files(fs, |_, _| true);
+    available.sort_by_key(|(_, size)| *size);
+    let mut taken: Vec<(String, usize)> = Vec::new();
+    let mut missing = bytes;
+    let mut best: Option<(Vec<(String, usize)>, usize)> = None;
+    loop {
+        let next = match available.iter().position(|(_, size)| *size >= missing) {
+            Some(covering) => {
+                let total = bytes - missing + available[covering].1;
+                if best.as_ref().is_none_or(|(_, t)| total < *t) {
+                    let mut files = taken.clone();
+                    files.push(available[covering].clone());
+                    best = Some((files, total));
+                }
+                match covering {
+                    0 => break,
+                    _ => available.remove(covering - 1),
+                }
+            }
+            None => match available.pop() {
+                Some(file) => file,
+                None => break,
+            },
+        };
+        missing -= next.1;
+        taken.push(next);
+    }
+
+    return match (directory, best) {
+        (Some(Plan::Directory(path, size)), Some((_, total))) if size <= total => {
+            Some(Plan::Directory(path, size))
+        }
+        (directory, None) => directory,
+        (_, Some((mut files, total))) => {
+            files.sort();
+            Some(Plan::Files(files, total))
+        }
+    };
+}
+
+fn find_dir<'a>(fs: &'a FileSystem, path: &str) -> Result<&'a Directory, String> {
+    match fs.lookup(path) {
+        Some(id) => Ok(fs.dir(id)),
+        None => Err(format!("no directory {}", path)),
+    }
+}
+
+/// Directory names may contain spaces, so a path is all remaining arguments.
+fn path_arg(args: &[&str]) -> Result<String, String> {
+    match args {
+        [] => Err("missing <path>".to_string()),
+        _ => Ok(args.join(" ")),
+    }
+}
+
+fn listing(entries: &[(String, usize)]) -> String {
+    return entries
+        .iter()
+        .map(|(path, size)| format!("{} {}", size, path))
+        .collect::<Vec<String>>()
+        .join("\n");
+}
+
+fn repl(fs: &FileSystem) -> Repl<'_, FileSystem> {
+    return Repl::new(fs)
+        .command("first", "", |fs, _| Ok(first(fs).to_string()))
+        .command("second", "", |fs, _| Ok(second(fs).to_string()))
+        .command("size", "<path>", |fs, args| {
+            let path = path_arg(args)?;
+            let dir = find_dir(fs, &path)?;
+            match dir.complete {
+                true => Ok(dir.size.to_string()),
+                false => Ok(format!("at least {}", dir.size)),
+            }
+        })
+        .command("resolve", "<path>", |fs, args| {
+            let path = path_arg(args)?;
+            let id = fs.lookup(&path).ok_or(format!("no directory {}", path))?;
+            Ok(fs.path(id))
+        })
+        .command("tree", "[path]", |fs, args| {
+            let path = path_arg(args).unwrap_or("/".to_string());
+            let id = fs.lookup(&path).ok_or(format!("no directory {}", path))?;
+            Ok(tree(fs, id))
+        })
+        .command("largest", "<n>", |fs, args| {
+            let n: usize = repl::arg(args, 0, "n")?;
+            Ok(listing(&largest_dirs(fs, n)))
+        })
+        .command(
+            "find",
+            "name <text> | min <size> | max <size>",
+            |fs, args| {
+                let found = match args.first().copied() {
+                    Some("name") => {
+                        let text: String = repl::arg(args, 1, "text")?;
+                        find_files(fs, |name, _| name.contains(&text))
+                    }
+                    Some("min") => {
+                        let min: usize = repl::arg(args, 1, "size")?;
+                        find_files(fs, |_, size| size >= min)
+                    }
+                    Some("max") => {
+                        let max: usize = repl::arg(args, 1, "size")?;
+                        find_files(fs, |_, size| size <= max)
+                    }
+                    _ => return Err("expected name, min or max".to_string()),
+                };
+                Ok(listing(&found))
+            },
+        )
+        .command("free", "<bytes>", |fs, args| {
+            let bytes: usize = repl::arg(args, 0, "bytes")?;
+            match plan_deletion(fs, bytes) {
+                Some(Plan::Directory(path, size)) => Ok(format!("delete {} ({})", path, size)),
+                Some(Plan::Files(files, total)) => Ok(format!(
+                    "delete {} files ({})\n{}",
+                    files.len(),
+                    total,
+                    listing(&files)
+                )),
+                None => Err(format!("cannot free {} bytes", bytes)),
+            }
+        })
+        .command("ls", "<path>", |fs, args| {
+            let path = path_arg(args)?;
+            let dir = find_dir(fs, &path)?;
+            Ok(dir
+                .dirs
+                .iter()
+                .map(|(name, id)| format!("{} {}", fs.dir(*id).size, name))
+                .collect::<Vec<String>>()
+                .join("\n"))
+        })
+        .command("files", "<path>", |fs, args| {
+            let path = path_arg(args)?;
+            let dir = find_dir(fs, &path)?;
+            Ok(dir
+                .files
+                .iter()
+                .map(|(name, size)| format!("{} {}", size, name))
+                .collect::<Vec<String>>()
+                .join("\n"))
+        });
 }
 
 fn main() {
-    let parsed = parse(&lines());
-    println!("first = {}", first(&parsed));
-    println!("second = {}", second(&parsed));
+    if let Some(path) = repl::requested() {
+        let parsed = cached("day07", &file_lines(&path), parse);
+        return repl(&parsed).run_stdio();
+    }
+
+    // `day07 check` reports problems with the transcript instead
+    if env::args().nth(1).as_deref() == Some("check") {
+        println!("{}", validate(&lines()));
+        return report_total();
+    }
+
+    let parsed = measured("parse", || cached("day07", &lines(), parse));
+    println!("first = {}", measured("first", || first(&parsed)));
+    println!("second = {}", measured("second", || second(&parsed)));
+    report_total();
 }
 
 #[cfg(test)]
@@ -134,4 +569,223 @@ $ ls
         let input = parse(&INPUT.lines().map(|l| l.to_string()).collect());
         assert_eq!(24933642, second(&input));
     }
+
+    #[test]
+    fn test_repl() {
+        let input = parse(&INPUT.lines().map(|l| l.to_string()).collect());
+        let repl = repl(&input);
+        assert_eq!(Some("48381165".to_string()), repl.execute("size /"));
+        assert_eq!(Some("584".to_string()), repl.execute("size /a/e"));
+        assert_eq!(
+            Some("94853 a\n24933642 d".to_string()),
+            repl.execute("ls /")
+        );
+        assert_eq!(
+            Some("5626152 d.ext\n8033020 d.log\n4060174 j\n7214296 k".to_string()),
+            repl.execute("files /d/../d")
+        );
+        assert_eq!(Some("/a".to_string()), repl.execute("resolve /a/e/.."));
+        assert_eq!(Some("48381165 /".to_string()), repl.execute("largest 1"));
+        assert_eq!(Some("584 /a/e/i".to_string()), repl.execute("find name i"));
+        assert_eq!(
+            Some("delete /a/e (584)".to_string()),
+            repl.execute("free 500")
+        );
+    }
+
+    #[test]
+    fn test_filesystem() {
+        let fs = parse(&INPUT.lines().map(|l| l.to_string()).collect());
+        let e = fs.lookup("/a/e").unwrap();
+        assert_eq!("/a/e", fs.path(e));
+        assert_eq!(Some(fs.lookup("/a").unwrap()), fs.dir(e).parent);
+        assert_eq!(Some(ROOT), fs.lookup("/a/e/../../.."));
+        assert_eq!(None, fs.lookup("/x"));
+        assert_eq!(None, fs.lookup("a"));
+        assert_eq!(Some(&584), fs.dir(e).files.get("i"));
+
+        // jumping back to the root and listing a directory twice
+        let transcript = "$ cd /
+$ ls
+dir a
+$ cd a
+$ ls
+10 x
+$ cd /
+$ cd a
+$ ls
+10 x
+20 y
+$ cd /
+$ ls
+dir a
+5 z";
+        let fs = parse(&transcript.lines().map(|l| l.to_string()).collect());
+        assert_eq!(2, fs.dirs.len());
+        assert_eq!(35, fs.dir(ROOT).size);
+        assert_eq!(30, fs.dir(fs.lookup("/a").unwrap()).size);
+    }
+
+    #[test]
+    fn test_queries() {
+        let fs = parse(&INPUT.lines().map(|l| l.to_string()).collect());
+        assert_eq!(
+            "- a (dir, size=94853)
+  - e (dir, size=584)
+    - i (file, size=584)
+  - f (file, size=29116)
+  - g (file, size=2557)
+  - h.lst (file, size=62596)",
+            tree(&fs, fs.lookup("/a").unwrap())
+        );
+        assert_eq!(
+            vec![("/".to_string(), 48381165), ("/d".to_string(), 24933642)],
+            largest_dirs(&fs, 2)
+        );
+        assert_eq!(
+            vec![("/a/e/i".to_string(), 584), ("/a/g".to_string(), 2557)],
+            find_files(&fs, |_, size| size < 10000)
+        );
+        assert_eq!(
+            vec![
+                ("/d/d.ext".to_string(), 5626152),
+                ("/d/d.log".to_string(), 8033020)
+            ],
+            find_files(&fs, |name, _| name.starts_with("d."))
+        );
+    }
+
+    #[test]
+    fn test_plan_deletion() {
+        let fs = parse(&INPUT.lines().map(|l| l.to_string()).collect());
+        assert_eq!(
+            Some(Plan::Directory("/a/e".to_string(), 584)),
+            plan_deletion(&fs, 584)
+        );
+        // deleting one file frees part two's space with less lost than /d
+        assert_eq!(
+            Some(Plan::Files(vec![("/c.dat".to_string(), 8504156)], 8504156)),
+            plan_deletion(&fs, 8381165)
+        );
+        // two files beat every directory that is big enough
+        assert_eq!(
+            Some(Plan::Files(
+                vec![("/a/f".to_string(), 29116), ("/a/h.lst".to_string(), 62596)],
+                91712
+            )),
+            plan_deletion(&fs, 90000)
+        );
+        assert_eq!(None, plan_deletion(&fs, 50000000));
+    }
+
+    #[test]
+    fn test_validate() {
+        let input: Vec<String> = INPUT.lines().map(|l| l.to_string()).collect();
+        assert_eq!("sizes are complete", validate(&input));
+        let (fs, issues) = read_transcript(&input);
+        assert!(issues.is_empty());
+        assert!(fs.dir(ROOT).complete);
+
+        let transcript = "$ cd /
+$ cd ..
+$ ls
+dir a
+10 x
+$ cd a
+$ ls
+dir b
+$ cd c
+$ cd /
+$ ls
+12 x
+oops
+$ cd a
+$ cd b
+7 y";
+        let input: Vec<String> = transcript.lines().map(|l| l.to_string()).collect();
+        let (fs, issues) = read_transcript(&input);
+        assert_eq!(
+            vec![
+                Issue::AboveRoot(2),
+                Issue::UnlistedDirectory(9, "/a/c".to_string()),
+                Issue::ConflictingSize(12, "/x".to_string(), 10, 12),
+                Issue::Unparseable(13),
+                Issue::OutsideListing(16),
+            ],
+            issues
+        );
+        // /a/b and /a/c were never listed, so everything above them may be bigger
+        assert!(!fs.dir(ROOT).complete);
+        assert!(!fs.dir(fs.lookup("/a").unwrap()).complete);
+        assert_eq!(
+            "line 2: cd .. from /
+line 9: cd into /a/c, which no listing showed
+line 12: /x listed with 12 bytes, earlier 10
+line 13: cannot parse
+line 16: output without ls
+sizes are uncertain: conflicting listings, unparseable lines
+sizes are lower bounds, never listed: /a/b, /a/c",
+            validate(&input)
+        );
+        assert_eq!(Some("at least 19".to_string()), repl(&fs).execute("size /"));
+
+        // everything listed, but not consistently
+        let input: Vec<String> = "$ cd /\n$ ls\n10 x\n$ ls\n12 x"
+            .lines()
+            .map(|l| l.to_string())
+            .collect();
+        assert_eq!(
+            "line 5: /x listed with 12 bytes, earlier 10
+sizes are uncertain: conflicting listings",
+            validate(&input)
+        );
+    }
+
+    #[test]
+    fn test_real_transcripts() {
+        let transcript = "$ cd /
+$ ls
+dir My Docs
+123 my file.txt
+$ cd My Docs
+$ ls
+dir a
+$ cd a/../a/./
+$ ls
+dir b
+7 notes
+$ cd /My Docs/a/b
+$ ls
+5 x y
+$ cd ../../..
+$ cd ..
+$ cd /missing/deeper";
+        let input: Vec<String> = transcript.lines().map(|l| l.to_string()).collect();
+        let (fs, issues) = read_transcript(&input);
+        assert_eq!(
+            vec![
+                Issue::AboveRoot(16),
+                Issue::UnlistedDirectory(17, "/missing".to_string()),
+                Issue::UnlistedDirectory(17, "/missing/deeper".to_string()),
+            ],
+            issues
+        );
+        assert_eq!(Some(&123), fs.dir(ROOT).files.get("my file.txt"));
+        let b = fs.lookup("/My Docs/a/b").unwrap();
+        assert_eq!(Some(&5), fs.dir(b).files.get("x y"));
+        assert_eq!(123 + 7 + 5, fs.dir(ROOT).size);
+        assert_eq!(12, fs.dir(fs.lookup("/My Docs").unwrap()).size);
+
+        let repl = repl(&fs);
+        assert_eq!(Some("12".to_string()), repl.execute("size /My Docs"));
+        assert_eq!(Some("12 a".to_string()), repl.execute("ls /My Docs"));
+        assert_eq!(
+            Some("5 x y".to_string()),
+            repl.execute("files /My Docs/a/b")
+        );
+        assert_eq!(
+            Some("/My Docs".to_string()),
+            repl.execute("resolve /My Docs/a/..")
+        );
+    }
 }
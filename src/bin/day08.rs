@@ -1,6 +1,10 @@
-use aoc_2022_rust::aoc::input::lines;
+use std::{cmp::Reverse, collections::BinaryHeap, env, fmt::Display};
 
-use std::cmp::max;
+use aoc_2022_rust::aoc::{
+    cache::cached,
+    input::lines,
+    memory::{measured, report_total},
+};
 
 type Input = Vec<Vec<u32>>;
 
@@ -14,6 +18,150 @@ fn parse(input: &Vec<String>) -> Input {
     return result;
 }
 
+/// Sides of the forest a tree can be seen from, one bit per side of its
+/// `Sight`.
+type Sides = u32;
+
+// Each side with the (row, column) step of looking from it into the forest.
+const ORTHOGONAL: [(&str, (i64, i64)); 4] =
+    [("N", (1, 0)), ("S", (-1, 0)), ("W", (0, 1)), ("E", (0, -1))];
+const DIAGONAL: [(&str, (i64, i64)); 4] = [
+    ("NW", (1, 1)),
+    ("NE", (1, -1)),
+    ("SW", (-1, 1)),
+    ("SE", (-1, -1)),
+];
+
+/// How the forest is looked at: the lines of sight, named after the side
+/// they come from, and how far above the top of their tree the eyes of a
+/// tree house viewer are. Looking in from outside is along the same lines
+/// but not affected by `eye`.
+struct Sight {
+    sides: Vec<(String, (i64, i64))>,
+    eye: i64,
+}
+
+impl Sight {
+    /// Rows and columns only, eyes at the height of the tree.
+    fn puzzle() -> Sight {
+        Sight {
+            sides: ORTHOGONAL
+                .iter()
+                .map(|(name, step)| (name.to_string(), *step))
+                .collect(),
+            eye: 0,
+        }
+    }
+
+    /// Adds a line of sight, up to one per bit of `Sides`.
+    fn add(&mut self, name: &str, step: (i64, i64)) -> Result<(), String> {
+        if self.sides.len() == Sides::BITS as usize {
+            return Err(format!("at most {} lines of sight", Sides::BITS));
+        }
+        if step == (0, 0) {
+            return Err(format!("{} does not move", name));
+        }
+        if self.sides.iter().any(|(n, s)| n == name || *s == step) {
+            return Err(format!("{} is already looked along", name));
+        }
+        self.sides.push((name.to_string(), step));
+        return Ok(());
+    }
+
+    fn with_diagonals(mut self) -> Sight {
+        for (name, step) in DIAGONAL {
+            self.add(name, step).unwrap();
+        }
+        return self;
+    }
+
+    fn describe(&self, sides: Sides) -> String {
+        let names: Vec<&str> = self
+            .sides
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| sides & (1 << i) != 0)
+            .map(|(_, (name, _))| name.as_str())
+            .collect();
+        return names.join(" ");
+    }
+}
+
+/// Per-tree results of looking along every line of a `Sight`.
+struct Survey {
+    visible: Vec<Vec<Sides>>,
+    scores: Vec<Vec<usize>>,
+}
+
+fn step(input: &Input, (row, col): (usize, usize), (dr, dc): (i64, i64)) -> Option<(usize, usize)> {
+    let row = row as i64 + dr;
+    let col = col as i64 + dc;
+    if row < 0 || col < 0 || row >= input.len() as i64 || col >= input[0].len() as i64 {
+        return None;
+    }
+    return Some((row as usize, col as usize));
+}
+
+/// Visibility from outside and scenic scores in O(n²) for an n×n forest.
+///
+/// Walks every line of trees once per side, starting at the edge the line
+/// enters from. A running maximum tells whether a tree can be seen from that
+/// edge, and a stack of the trees not yet blocked, in decreasing height,
+/// tells how far it can see back towards that edge. Heights are single
+/// digits, so the stack never holds more than ten trees.
+fn survey(input: &Input, sight: &Sight) -> Survey {
+    let rows = input.len();
+    let cols = input[0].len();
+    let mut visible = vec![vec![0; cols]; rows];
+    let mut scores = vec![vec![1usize; cols]; rows];
+
+    let mut stack: Vec<(i64, usize)> = Vec::new();
+    for (side, (_, direction)) in sight.sides.iter().enumerate() {
+        let direction = *direction;
+        let back = (-direction.0, -direction.1);
+        for row in 0..rows {
+            for col in 0..cols {
+                if step(input, (row, col), back).is_some() {
+                    continue;
+                }
+
+                stack.clear();
+                let mut tallest: Option<i64> = None;
+                let mut position = Some((row, col));
+                let mut distance = 0;
+                while let Some((r, c)) = position {
+                    let height = input[r][c] as i64;
+                    if tallest.is_none_or(|t| height > t) {
+                        visible[r][c] |= 1 << side;
+                        tallest = Some(height);
+                    }
+
+                    // the nearest tree reaching the eyes blocks the view
+                    let reaching = stack.partition_point(|(h, _)| *h >= height + sight.eye);
+                    // many long lines of sight can outgrow any integer
+                    scores[r][c] = scores[r][c].saturating_mul(match reaching.checked_sub(1) {
+                        Some(blocker) => distance - stack[blocker].1,
+                        None => distance,
+                    });
+
+                    // lower trees behind this one can no longer block anything
+                    while stack.last().is_some_and(|(h, _)| *h <= height) {
+                        stack.pop();
+                    }
+                    stack.push((height, distance));
+
+                    distance += 1;
+                    position = step(input, (r, c), direction);
+                }
+            }
+        }
+    }
+
+    return Survey { visible, scores };
+}
+
+// The straightforward versions, kept to check `survey` against.
+#[cfg(test)]
 fn is_visible(input: &Input, row: usize, col: usize) -> bool {
     if row == 0 || col == 0 || row == input.len() - 1 || col == input[0].len() - 1 {
         return true;
@@ -42,19 +190,7 @@ fn is_visible(input: &Input, row: usize, col: usize) -> bool {
     return false;
 }
 
-fn first(input: &Input) -> usize {
-    let mut visible_trees = 0;
-    for row in 0..input.len() {
-        for col in 0..input[row].len() {
-            if is_visible(input, row, col) {
-                visible_trees += 1;
-            }
-        }
-    }
-
-    return visible_trees;
-}
-
+#[cfg(test)]
 fn scenic_score(input: &Input, row: usize, col: usize) -> usize {
     let mut score = 1;
 
@@ -81,26 +217,233 @@ fn scenic_score(input: &Input, row: usize, col: usize) -> usize {
     return score;
 }
 
+// Looks along every line of `sight` one tree at a time.
+#[cfg(test)]
+fn look(input: &Input, sight: &Sight, row: usize, col: usize) -> (Sides, usize) {
+    let height = input[row][col] as i64;
+    let mut visible = 0;
+    let mut score: usize = 1;
+    for (side, (_, (dr, dc))) in sight.sides.iter().enumerate() {
+        // towards the side the line comes from
+        let mut trees = Vec::new();
+        let mut position = step(input, (row, col), (-dr, -dc));
+        while let Some((r, c)) = position {
+            trees.push(input[r][c] as i64);
+            position = step(input, (r, c), (-dr, -dc));
+        }
+
+        if trees.iter().all(|h| *h < height) {
+            visible |= 1 << side;
+        }
+        score = score.saturating_mul(match trees.iter().position(|h| *h >= height + sight.eye) {
+            Some(blocker) => blocker + 1,
+            None => trees.len(),
+        });
+    }
+
+    return (visible, score);
+}
+
+fn first(input: &Input) -> usize {
+    return survey(input, &Sight::puzzle())
+        .visible
+        .iter()
+        .flatten()
+        .filter(|v| **v != 0)
+        .count();
+}
+
 fn second(input: &Input) -> usize {
-    let mut best_score = 0;
-    for row in 0..input.len() {
-        for col in 0..input[row].len() {
-            best_score = max(best_score, scenic_score(input, row, col));
+    return survey(input, &Sight::puzzle())
+        .scores
+        .iter()
+        .flatten()
+        .copied()
+        .max()
+        .unwrap_or(0);
+}
+
+/// The `k` best scenic spots as (row, column, score), best first and in
+/// reading order among equal scores.
+fn top_spots(survey: &Survey, k: usize) -> Vec<(usize, usize, usize)> {
+    if k == 0 {
+        return Vec::new();
+    }
+
+    // the spots kept so far, the worst of them on top
+    let mut kept: BinaryHeap<(Reverse<usize>, usize, usize)> = BinaryHeap::new();
+    for (row, scores) in survey.scores.iter().enumerate() {
+        for (col, score) in scores.iter().enumerate() {
+            kept.push((Reverse(*score), row, col));
+            if kept.len() > k {
+                kept.pop();
+            }
         }
     }
 
-    return best_score;
+    return kept
+        .into_sorted_vec()
+        .into_iter()
+        .map(|(Reverse(score), row, col)| (row, col, score))
+        .collect();
+}
+
+fn to_csv<T>(grid: &Vec<Vec<T>>, cell: impl Fn(&T) -> String) -> String {
+    let mut result = String::new();
+    for row in grid {
+        let cells: Vec<String> = row.iter().map(&cell).collect();
+        result += &cells.join(",");
+        result += "\n";
+    }
+
+    return result;
+}
+
+// Plain (ASCII) netpbm image, `P2` for gray and `P3` for colour pixels.
+fn to_netpbm<T: Display>(magic: &str, max: usize, pixels: &Vec<Vec<T>>) -> String {
+    let mut result = format!("{}\n{} {}\n{}\n", magic, pixels[0].len(), pixels.len(), max);
+    for row in pixels {
+        let cells: Vec<String> = row.iter().map(|p| p.to_string()).collect();
+        result += &cells.join(" ");
+        result += "\n";
+    }
+
+    return result;
+}
+
+/// Scenic scores as a gray image, brightest at the best score. Scores above
+/// the largest PGM value are scaled down.
+fn scores_pgm(survey: &Survey) -> String {
+    let best = survey
+        .scores
+        .iter()
+        .flatten()
+        .copied()
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let max = best.min(u16::MAX as usize);
+    let pixels: Vec<Vec<usize>> = survey
+        .scores
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|s| (*s as u128 * max as u128 / best as u128) as usize)
+                .collect()
+        })
+        .collect();
+
+    return to_netpbm("P2", max, &pixels);
+}
+
+/// Visibility as a colour image: red for trees seen along a column, blue
+/// along a row and green along a diagonal, mixed when seen along several,
+/// and hidden trees in gray shades of their height.
+fn visibility_ppm(input: &Input, sight: &Sight, survey: &Survey) -> String {
+    // which sides add to each colour
+    let mut channels: [Sides; 3] = [0; 3];
+    for (side, (_, (dr, dc))) in sight.sides.iter().enumerate() {
+        let channel = match (*dr != 0, *dc != 0) {
+            (true, false) => 0,
+            (false, true) => 2,
+            _ => 1,
+        };
+        channels[channel] |= 1 << side;
+    }
+
+    let pixels: Vec<Vec<String>> = survey
+        .visible
+        .iter()
+        .zip(input)
+        .map(|(sides, heights)| {
+            sides
+                .iter()
+                .zip(heights)
+                .map(|(sides, height)| {
+                    let shade = 20 * height;
+                    if *sides == 0 {
+                        return format!("{} {} {}", shade, shade, shade);
+                    }
+                    let [r, g, b] = channels.map(|c| if sides & c != 0 { 255 } else { 0 });
+                    format!("{} {} {}", r, g, b)
+                })
+                .collect()
+        })
+        .collect();
+
+    return to_netpbm("P3", 255, &pixels);
 }
 
 fn main() {
-    let parsed = parse(&lines());
-    println!("first = {}", first(&parsed));
-    println!("second = {}", second(&parsed));
+    // `day08 [diagonal] [line <name> <dr> <dc>]... [eye <n>] ...` changes how
+    // the forest is looked at; then `visible csv|ppm` and `scores csv|pgm`
+    // print the per-tree results, `top <k>` the best scenic spots and
+    // nothing both answers
+    let args: Vec<String> = env::args().skip(1).collect();
+    if !args.is_empty() {
+        let mut args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+        let mut sight = Sight::puzzle();
+        loop {
+            match args.as_slice() {
+                ["diagonal", ..] => {
+                    sight = sight.with_diagonals();
+                    args.remove(0);
+                }
+                ["line", name, dr, dc, ..] => {
+                    let step = (
+                        dr.parse().expect("expected a row step"),
+                        dc.parse().expect("expected a column step"),
+                    );
+                    if let Err(e) = sight.add(name, step) {
+                        panic!("Bad line of sight: {}", e);
+                    }
+                    args.drain(..4);
+                }
+                ["eye", n, ..] => {
+                    sight.eye = n.parse().expect("expected an eye height");
+                    args.drain(..2);
+                }
+                _ => break,
+            }
+        }
+
+        let parsed = cached("day08", &lines(), parse);
+        let survey = survey(&parsed, &sight);
+        match args.as_slice() {
+            [] => {
+                let visible = survey.visible.iter().flatten().filter(|v| **v != 0);
+                println!("visible = {}", visible.count());
+                let best = survey.scores.iter().flatten().max();
+                println!("best = {}", best.copied().unwrap_or(0));
+            }
+            ["visible", "csv"] => print!("{}", to_csv(&survey.visible, |s| sight.describe(*s))),
+            ["visible", "ppm"] => print!("{}", visibility_ppm(&parsed, &sight, &survey)),
+            ["scores", "csv"] => print!("{}", to_csv(&survey.scores, |s| s.to_string())),
+            ["scores", "pgm"] => print!("{}", scores_pgm(&survey)),
+            ["top", k] => {
+                let k = k.parse().expect("expected a number of spots");
+                for (row, col, score) in top_spots(&survey, k) {
+                    println!("{},{}: {}", row, col, score);
+                }
+            }
+            _ => panic!(
+                "usage: day08 [diagonal] [line <name> <dr> <dc>]... [eye <n>] \
+                 [visible csv|ppm | scores csv|pgm | top <k>]"
+            ),
+        }
+        return;
+    }
+
+    let parsed = measured("parse", || cached("day08", &lines(), parse));
+    println!("first = {}", measured("first", || first(&parsed)));
+    println!("second = {}", measured("second", || second(&parsed)));
+    report_total();
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     const INPUT: &str = "30373
 25512
@@ -119,4 +462,121 @@ mod tests {
         let input = parse(&INPUT.lines().map(|l| l.to_string()).collect());
         assert_eq!(8, second(&input));
     }
+
+    #[test]
+    fn test_outputs() {
+        let input = parse(&INPUT.lines().map(|l| l.to_string()).collect());
+        let sight = Sight::puzzle();
+        let survey = survey(&input, &sight);
+
+        let visible = to_csv(&survey.visible, |s| sight.describe(*s));
+        assert_eq!(Some("N W,N,N,N W E,N E"), visible.lines().next());
+        assert_eq!(Some("W,N W,N E,,E"), visible.lines().nth(1));
+        assert_eq!(vec![(3, 2, 8), (2, 1, 6), (1, 2, 4)], top_spots(&survey, 3));
+
+        assert!(top_spots(&survey, 0).is_empty());
+        assert_eq!(25, top_spots(&survey, 30).len());
+        // equal scores in reading order
+        let all = top_spots(&survey, 17);
+        assert_eq!(((0, 0, 0), (2, 0, 0)), (all[9], all[16]));
+
+        let pgm = scores_pgm(&survey);
+        assert!(pgm.starts_with("P2\n5 5\n8\n0 0 0 0 0\n0 1 4 1 0\n"));
+        let ppm = visibility_ppm(&input, &sight, &survey);
+        assert_eq!(
+            Some("0 0 255 255 0 255 255 0 255 20 20 20 0 0 255"),
+            ppm.lines().nth(4)
+        );
+    }
+
+    #[test]
+    fn test_sight() {
+        let input = parse(&INPUT.lines().map(|l| l.to_string()).collect());
+        let mut sight = Sight::puzzle().with_diagonals();
+        let diagonal = survey(&input, &sight);
+
+        // the 5 next to the top edge can also be seen from the north-west
+        assert_eq!("N E NW", sight.describe(diagonal.visible[1][2]));
+        assert_eq!(vec![(3, 2, 16)], top_spots(&diagonal, 1));
+
+        // eyes above every tree see to the edges
+        sight.eye = 10;
+        let above = survey(&input, &sight);
+        assert_eq!(2usize.pow(8), above.scores[2][2]);
+        assert_eq!(diagonal.visible, above.visible);
+    }
+
+    #[test]
+    fn test_sight_limits() {
+        let mut sight = Sight::puzzle().with_diagonals();
+        assert!(sight.add("up", (1, 0)).is_err());
+        assert!(sight.add("N", (5, 0)).is_err());
+        assert!(sight.add("still", (0, 0)).is_err());
+        assert_eq!(Ok(()), sight.add("knight", (2, 1)));
+
+        for i in 0..23 {
+            assert_eq!(Ok(()), sight.add(&format!("far{}", i), (i + 3, 0)));
+        }
+        assert_eq!(
+            Err("at most 32 lines of sight".to_string()),
+            sight.add("one more", (0, 7))
+        );
+
+        // long lines of sight in a large, flat forest overflow the scores
+        let input = vec![vec![1; 200]; 200];
+        let mut wide = Sight::puzzle().with_diagonals();
+        for (i, step) in [(0, 2), (0, 3), (0, 4), (2, 0), (3, 0)].iter().enumerate() {
+            wide.add(&format!("far{}", i), *step).unwrap();
+        }
+        wide.eye = 5;
+        let survey = survey(&input, &wide);
+        assert_eq!(usize::MAX, survey.scores[100][100]);
+        assert_eq!(0, survey.scores[0][100]);
+    }
+
+    fn forest() -> impl Strategy<Value = Input> {
+        (1usize..8, 1usize..8).prop_flat_map(|(rows, cols)| {
+            prop::collection::vec(prop::collection::vec(0u32..10, cols), rows)
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn survey_matches_reference(input in forest()) {
+            let survey = survey(&input, &Sight::puzzle());
+            for row in 0..input.len() {
+                for col in 0..input[0].len() {
+                    prop_assert_eq!(is_visible(&input, row, col), survey.visible[row][col] != 0);
+                    prop_assert_eq!(scenic_score(&input, row, col), survey.scores[row][col]);
+                }
+            }
+        }
+
+        #[test]
+        fn sight_matches_reference(
+            input in forest(),
+            diagonal: bool,
+            steps in prop::collection::vec((-3i64..4, -3i64..4), 0..4),
+            eye in -3i64..5,
+        ) {
+            let mut sight = Sight::puzzle();
+            if diagonal {
+                sight = sight.with_diagonals();
+            }
+            for (i, step) in steps.into_iter().enumerate() {
+                // zero and repeated steps are refused
+                let _ = sight.add(&format!("L{}", i), step);
+            }
+            sight.eye = eye;
+            let survey = survey(&input, &sight);
+            for row in 0..input.len() {
+                for col in 0..input[0].len() {
+                    prop_assert_eq!(
+                        look(&input, &sight, row, col),
+                        (survey.visible[row][col], survey.scores[row][col])
+                    );
+                }
+            }
+        }
+    }
 }
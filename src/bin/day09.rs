@@ -1,7 +1,13 @@
 use std::collections::HashSet;
 
-use aoc_2022_rust::aoc::input::lines;
-
+use aoc_2022_rust::aoc::{
+    cache::cached,
+    input::lines,
+    memory::{measured, report_total},
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize)]
 struct Instruction {
     dx: i32,
     dy: i32,
@@ -81,6 +87,15 @@ fn maybe_move(head: &Position, tail: &Position) -> Position {
     return Position { x, y };
 }
 
+fn step(rope: &mut Vec<Position>, instr: &Instruction) {
+    rope[0].x += instr.dx;
+    rope[0].y += instr.dy;
+
+    for i in 1..rope.len() {
+        rope[i] = maybe_move(&rope[i - 1], &rope[i]);
+    }
+}
+
 fn move_rope_of_length(instructions: &Input, length: usize) -> usize {
     let mut rope = vec![Position { x: 0, y: 0 }; length];
 
@@ -89,12 +104,7 @@ fn move_rope_of_length(instructions: &Input, length: usize) -> usize {
 
     for instr in instructions {
         for _ in 0..instr.count {
-            rope[0].x += instr.dx;
-            rope[0].y += instr.dy;
-
-            for i in 1..length {
-                rope[i] = maybe_move(&rope[i - 1], &rope[i]);
-            }
+            step(&mut rope, instr);
 
             let tail = rope.last().unwrap();
             visited_by_tail.insert((tail.x, tail.y));
@@ -113,14 +123,16 @@ fn second(instructions: &Input) -> usize {
 }
 
 fn main() {
-    let parsed = parse(&lines());
-    println!("first = {}", first(&parsed));
-    println!("second = {}", second(&parsed));
+    let parsed = measured("parse", || cached("day09", &lines(), parse));
+    println!("first = {}", measured("first", || first(&parsed)));
+    println!("second = {}", measured("second", || second(&parsed)));
+    report_total();
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     const INPUT1: &str = "R 4
 U 4
@@ -152,4 +164,21 @@ U 20";
         let input2 = parse(&INPUT2.lines().map(|l| l.to_string()).collect());
         assert_eq!(36, second(&input2));
     }
+
+    proptest! {
+        #[test]
+        fn tail_follows_head(moves in prop::collection::vec((0usize..4, 1usize..6), 1..40), length in 2usize..11) {
+            let mut rope = vec![Position { x: 0, y: 0 }; length];
+            for (dir, count) in moves {
+                let (dx, dy) = [(0, -1), (0, 1), (-1, 0), (1, 0)][dir];
+                for _ in 0..count {
+                    step(&mut rope, &Instruction { dx, dy, count: 1 });
+                    for pair in rope.windows(2) {
+                        prop_assert!((pair[0].x - pair[1].x).abs() <= 1);
+                        prop_assert!((pair[0].y - pair[1].y).abs() <= 1);
+                    }
+                }
+            }
+        }
+    }
 }
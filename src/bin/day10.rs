@@ -1,5 +1,11 @@
-use aoc_2022_rust::aoc::input::lines;
+use aoc_2022_rust::aoc::{
+    cache::cached,
+    input::lines,
+    memory::{measured, report_total},
+};
+use serde::{Deserialize, Serialize};
 
+#[derive(Deserialize, Serialize)]
 enum Instruction {
     Noop,
     Addx(i32),
@@ -98,14 +104,16 @@ fn second(instructions: &Input) -> String {
 }
 
 fn main() {
-    let parsed = parse(&lines());
-    println!("first = {}", first(&parsed));
-    println!("second = {}", second(&parsed));
+    let parsed = measured("parse", || cached("day10", &lines(), parse));
+    println!("first = {}", measured("first", || first(&parsed)));
+    println!("second = {}", measured("second", || second(&parsed)));
+    report_total();
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use aoc_2022_rust::aoc::snapshot::assert_snapshot;
 
     const INPUT: &str = "addx 15
 addx -11
@@ -263,16 +271,6 @@ noop";
     #[test]
     fn test_second() {
         let input = parse(&INPUT.lines().map(|l| l.to_string()).collect());
-        assert_eq!(
-            r"
-##..##..##..##..##..##..##..##..##..##..
-###...###...###...###...###...###...###.
-####....####....####....####....####....
-#####.....#####.....#####.....#####.....
-######......######......######......####
-#######.......#######.......#######.....
-",
-            second(&input)
-        );
+        assert_snapshot("day10_second", &second(&input));
     }
 }
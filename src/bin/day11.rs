@@ -1,22 +1,27 @@
 use std::slice::Iter;
 
-use aoc_2022_rust::aoc::input::lines;
-
-#[derive(Clone)]
+use aoc_2022_rust::aoc::{
+    cache::cached,
+    input::lines,
+    memory::{measured, report_total},
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Deserialize, Serialize)]
 enum Operation {
     Add(i64),
     MultiplyBy(i64),
     MultiplyByItself,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Deserialize, Serialize)]
 struct Test {
     divisible_by: i64,
     if_true: usize,
     if_false: usize,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Deserialize, Serialize)]
 struct Monkey {
     items: Vec<i64>,
     operation: Operation,
@@ -172,9 +177,10 @@ fn second(monkeys: Input) -> usize {
 }
 
 fn main() {
-    let parsed = parse(&lines());
-    println!("first = {}", first(parsed.clone()));
-    println!("second = {}", second(parsed));
+    let parsed = measured("parse", || cached("day11", &lines(), parse));
+    println!("first = {}", measured("first", || first(parsed.clone())));
+    println!("second = {}", measured("second", || second(parsed)));
+    report_total();
 }
 
 #[cfg(test)]
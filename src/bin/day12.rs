@@ -1,9 +1,15 @@
 use std::collections::{HashMap, VecDeque};
 
-use aoc_2022_rust::aoc::input::lines;
+use aoc_2022_rust::aoc::{
+    cache::cached,
+    input::lines,
+    memory::{measured, report_total},
+};
+use serde::{Deserialize, Serialize};
 
 type Point = (usize, usize);
 
+#[derive(Deserialize, Serialize)]
 struct HeightMap {
     start: Point,
     finish: Point,
@@ -124,9 +130,10 @@ fn second(map: &HeightMap) -> usize {
 }
 
 fn main() {
-    let parsed = parse(&lines());
-    println!("first = {}", first(&parsed));
-    println!("second = {}", second(&parsed));
+    let parsed = measured("parse", || cached("day12", &lines(), parse));
+    println!("first = {}", measured("first", || first(&parsed)));
+    println!("second = {}", measured("second", || second(&parsed)));
+    report_total();
 }
 
 #[cfg(test)]
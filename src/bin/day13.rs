@@ -1,8 +1,14 @@
 use std::cmp::Ordering;
 
-use aoc_2022_rust::aoc::input::lines;
-
-#[derive(Debug, Clone)]
+use aoc_2022_rust::aoc::{
+    cache::cached,
+    input::{file_lines, lines},
+    memory::{measured, report_total},
+    repl::{self, Repl},
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 enum Packet {
     Empty,
     Integer(i32),
@@ -132,15 +138,42 @@ fn second(pairs: &Packets) -> usize {
         .fold(1, |a, b| a * b);
 }
 
+fn repl(input: &Packets) -> Repl<'_, Packets> {
+    return Repl::new(input)
+        .command("first", "", |input, _| Ok(first(input).to_string()))
+        .command("second", "", |input, _| Ok(second(input).to_string()))
+        .command("compare", "<packet> <packet>", |_, args| {
+            let lhs: String = repl::arg(args, 0, "packet")?;
+            let rhs: String = repl::arg(args, 1, "packet")?;
+            let (_, lhs) = Packet::parse(&lhs);
+            let (_, rhs) = Packet::parse(&rhs);
+            Ok(format!("{:?}", lhs.cmp(&rhs)))
+        })
+        .command("pair", "<index>", |pairs, args| {
+            let index: usize = repl::arg(args, 0, "index")?;
+            let (lhs, rhs) = pairs
+                .get(index.wrapping_sub(1))
+                .ok_or(format!("no pair {}", index))?;
+            Ok(format!("{:?}\n{:?}\n{:?}", lhs, rhs, lhs.cmp(rhs)))
+        });
+}
+
 fn main() {
-    let parsed = parse(&lines());
-    println!("first = {}", first(&parsed));
-    println!("second = {}", second(&parsed));
+    if let Some(path) = repl::requested() {
+        let parsed = cached("day13", &file_lines(&path), parse);
+        return repl(&parsed).run_stdio();
+    }
+
+    let parsed = measured("parse", || cached("day13", &lines(), parse));
+    println!("first = {}", measured("first", || first(&parsed)));
+    println!("second = {}", measured("second", || second(&parsed)));
+    report_total();
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     const INPUT: &str = "[1,1,3,1,1]
 [1,1,5,1,1]
@@ -177,4 +210,47 @@ mod tests {
         let input = parse(&INPUT.lines().map(|l| l.to_string()).collect());
         assert_eq!(140, second(&input));
     }
+
+    #[test]
+    fn test_repl() {
+        let input = parse(&INPUT.lines().map(|l| l.to_string()).collect());
+        let repl = repl(&input);
+        assert_eq!(
+            Some("Less".to_string()),
+            repl.execute("compare [[1],[2,3,4]] [[1],4]")
+        );
+        assert_eq!(
+            Some("Greater".to_string()),
+            repl.execute("compare [9] [[8,7,6]]")
+        );
+        assert_eq!(
+            Some("error: no pair 9\nusage: pair <index>".to_string()),
+            repl.execute("pair 9")
+        );
+    }
+
+    fn packet() -> impl Strategy<Value = Packet> {
+        let leaf = (0..11).prop_map(|n: i32| n.to_string());
+        let text = leaf.prop_recursive(4, 32, 4, |inner| {
+            prop::collection::vec(inner, 0..4).prop_map(|items| format!("[{}]", items.join(",")))
+        });
+        text.prop_map(|t| Packet::parse(&format!("[{}]", t)).1)
+    }
+
+    proptest! {
+        #[test]
+        fn packet_order_is_antisymmetric(a in packet(), b in packet()) {
+            prop_assert_eq!(Ordering::Equal, a.cmp(&a));
+            prop_assert_eq!(a.cmp(&b), b.cmp(&a).reverse());
+        }
+
+        #[test]
+        fn packet_order_is_transitive(a in packet(), b in packet(), c in packet()) {
+            for (x, y, z) in [(&a, &b, &c), (&a, &c, &b), (&b, &a, &c), (&b, &c, &a), (&c, &a, &b), (&c, &b, &a)] {
+                if x <= y && y <= z {
+                    prop_assert!(x <= z);
+                }
+            }
+        }
+    }
 }
@@ -1,8 +1,13 @@
 use std::collections::HashMap;
 
-use aoc_2022_rust::aoc::input::lines;
-
-#[derive(Copy, Clone, PartialEq, Debug)]
+use aoc_2022_rust::aoc::{
+    cache::{cached, Entries},
+    input::lines,
+    memory::{measured, report_total},
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, PartialEq, Debug, Deserialize, Serialize)]
 enum Material {
     Air,
     Sand,
@@ -49,7 +54,7 @@ fn parse(input: &Vec<String>) -> Cave {
     return cave;
 }
 
-fn sand_simulator(c: &Cave, max_depth: i32, occupied: &dyn Fn(&Position, &Cave) -> bool) -> usize {
+fn sand_simulator(c: &Cave, max_depth: i32, occupied: &dyn Fn(&Position, &Cave) -> bool) -> Cave {
     let source: Position = (500, 0);
     let mut cave = c.clone();
 
@@ -73,10 +78,36 @@ fn sand_simulator(c: &Cave, max_depth: i32, occupied: &dyn Fn(&Position, &Cave)
             break;
         }
     }
+    return cave;
+}
+
+fn count_sand(cave: &Cave) -> usize {
     return cave.iter().filter(|(_, m)| **m == Material::Sand).count();
 }
 
-fn first(c: &Cave) -> usize {
+#[cfg(test)]
+fn render(cave: &Cave) -> String {
+    let source: Position = (500, 0);
+    let xs = cave.keys().map(|(x, _)| *x).chain([source.0]);
+    let (min_x, max_x) = (xs.clone().min().unwrap(), xs.max().unwrap());
+    let max_y = cave.keys().map(|(_, y)| *y).max().unwrap_or(0);
+
+    let mut result = String::new();
+    for y in 0..=max_y {
+        for x in min_x..=max_x {
+            result.push(match cave.get(&(x, y)) {
+                Some(Material::Rock) => '#',
+                Some(Material::Sand) => 'o',
+                _ if (x, y) == source => '+',
+                _ => '.',
+            });
+        }
+        result.push('\n');
+    }
+    return result;
+}
+
+fn fill_into_abyss(c: &Cave) -> Cave {
     let max_depth = *c.iter().map(|((_, py), _)| py).max().unwrap();
     let occupied = |position: &Position, cave: &Cave| -> bool {
         cave.contains_key(position) && cave[position] != Material::Air
@@ -85,7 +116,7 @@ fn first(c: &Cave) -> usize {
     return sand_simulator(&c, max_depth, &occupied);
 }
 
-fn second(c: &Cave) -> usize {
+fn fill_onto_floor(c: &Cave) -> Cave {
     let max_depth = *c.iter().map(|((_, py), _)| py).max().unwrap() + 2;
 
     let occupied = |position: &Position, cave: &Cave| -> bool {
@@ -95,15 +126,28 @@ fn second(c: &Cave) -> usize {
     return sand_simulator(&c, max_depth, &occupied);
 }
 
+fn first(c: &Cave) -> usize {
+    return count_sand(&fill_into_abyss(c));
+}
+
+fn second(c: &Cave) -> usize {
+    return count_sand(&fill_onto_floor(c));
+}
+
 fn main() {
-    let parsed = parse(&lines());
-    println!("first = {}", first(&parsed));
-    println!("second = {}", second(&parsed));
+    // stored as entries, since JSON has no tuple keys
+    let parsed = measured("parse", || {
+        cached("day14", &lines(), |l| Entries(parse(l))).0
+    });
+    println!("first = {}", measured("first", || first(&parsed)));
+    println!("second = {}", measured("second", || second(&parsed)));
+    report_total();
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use aoc_2022_rust::aoc::snapshot::assert_snapshot;
 
     const INPUT: &str = "498,4 -> 498,6 -> 496,6
 503,4 -> 502,4 -> 502,9 -> 494,9";
@@ -119,4 +163,11 @@ mod tests {
         let input = parse(&INPUT.lines().map(|l| l.to_string()).collect());
         assert_eq!(93, second(&input));
     }
+
+    #[test]
+    fn test_render() {
+        let input = parse(&INPUT.lines().map(|l| l.to_string()).collect());
+        assert_snapshot("day14_abyss", &render(&fill_into_abyss(&input)));
+        assert_snapshot("day14_floor", &render(&fill_onto_floor(&input)));
+    }
 }
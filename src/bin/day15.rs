@@ -1,6 +1,11 @@
 use std::collections::HashSet;
 
-use aoc_2022_rust::aoc::input::lines;
+use aoc_2022_rust::aoc::{
+    cache::cached,
+    input::lines,
+    interval::{Interval, IntervalSet},
+    memory::{measured, report_total},
+};
 
 type Position = (i64, i64);
 type Input = Vec<(Position, Position)>;
@@ -29,41 +34,29 @@ fn distance(a: &Position, b: &Position) -> i64 {
     return (a.0 - b.0).abs() + (a.1 - b.1).abs();
 }
 
-fn first(input: &Input, searched_y: i64) -> usize {
-    let mut not_occupied = HashSet::<Position>::new();
-    let mut beacons = HashSet::<Position>::new();
-    for (_, b) in input {
-        beacons.insert(*b);
-    }
-
-    for (s, b) in input {
-        let dist = distance(s, b);
-
-        let (sx, sy) = s;
-
-        for dy in -dist..=dist {
-            let y = sy + dy;
-            if y != searched_y {
-                continue;
+/// Positions on row `y` that are within reach of some sensor.
+fn row_coverage(input: &Input, y: i64) -> IntervalSet {
+    return input
+        .iter()
+        .filter_map(|(sensor, beacon)| {
+            let reach = distance(sensor, beacon) - (sensor.1 - y).abs();
+            if reach < 0 {
+                return None;
             }
-            for dx in -dist..=dist {
-                if dx.abs() + dy.abs() > dist || (dx == 0 && dy == 0) {
-                    continue;
-                }
-                let x = sx + dx;
-
-                if beacons.contains(&(x, y)) {
-                    continue;
-                }
+            return Some(Interval::new(sensor.0 - reach, sensor.0 + reach));
+        })
+        .collect();
+}
 
-                not_occupied.insert((x, y));
-            }
-        }
-    }
-    return not_occupied
+fn first(input: &Input, searched_y: i64) -> usize {
+    let coverage = row_coverage(input, searched_y);
+    let beacons: HashSet<i64> = input
         .iter()
-        .filter(|(_, py)| *py == searched_y)
-        .count();
+        .map(|(_, b)| *b)
+        .filter(|(x, y)| *y == searched_y && coverage.contains_point(*x))
+        .map(|(x, _)| x)
+        .collect();
+    return coverage.covered() as usize - beacons.len();
 }
 
 fn second(input: &Input, at_most: i64) -> i64 {
@@ -102,9 +95,13 @@ fn second(input: &Input, at_most: i64) -> i64 {
 }
 
 fn main() {
-    let parsed = parse(&lines());
-    println!("first = {}", first(&parsed, 2000000));
-    println!("second = {}", second(&parsed, 4000000));
+    let parsed = measured("parse", || cached("day15", &lines(), parse));
+    println!("first = {}", measured("first", || first(&parsed, 2000000)));
+    println!(
+        "second = {}",
+        measured("second", || second(&parsed, 4000000))
+    );
+    report_total();
 }
 
 #[cfg(test)]
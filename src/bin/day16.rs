@@ -1,8 +1,13 @@
 use std::collections::HashMap;
 
-use aoc_2022_rust::aoc::input::lines;
-
-#[derive(Clone)]
+use aoc_2022_rust::aoc::{
+    cache::cached,
+    input::lines,
+    memory::{measured, report_total},
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Deserialize, Serialize)]
 struct Position {
     valve: String,
     flow: i32,
@@ -103,9 +108,10 @@ fn second(scan: Scan) -> i32 {
 }
 
 fn main() {
-    let parsed = parse(&lines());
-    println!("first = {}", first(parsed.clone()));
-    println!("second = {}", second(parsed.clone()));
+    let parsed = measured("parse", || cached("day16", &lines(), parse));
+    println!("first = {}", measured("first", || first(parsed.clone())));
+    println!("second = {}", measured("second", || second(parsed.clone())));
+    report_total();
 }
 
 #[cfg(test)]
@@ -3,9 +3,14 @@ use std::{
     hash::{Hash, Hasher},
 };
 
-use aoc_2022_rust::aoc::input::as_str;
+use aoc_2022_rust::aoc::{
+    cache::cached,
+    input::as_str,
+    memory::{measured, report_total},
+};
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 struct Buffer<T> {
     elements: Vec<T>,
     next: usize,
@@ -101,6 +106,19 @@ impl Rock {
             chamber.push(0);
         }
     }
+
+    fn drop(&self, chamber: &mut Chamber, jets: &mut Jets) {
+        let mut position: Offset = (2, chamber.len() as i32);
+        loop {
+            position = self.try_push(chamber, &position, jets.next());
+            if let Some(new_position) = self.try_fall(chamber, &position) {
+                position = new_position;
+            } else {
+                self.freeze(chamber, &position);
+                break;
+            }
+        }
+    }
 }
 
 impl<T> Buffer<T> {
@@ -135,6 +153,20 @@ fn chamber_height(chamber: &Chamber) -> usize {
     return chamber.len() - empty - 1;
 }
 
+#[cfg(test)]
+fn render(chamber: &Chamber) -> String {
+    let mut result = String::new();
+    for row in chamber[1..=chamber_height(chamber)].iter().rev() {
+        result.push('|');
+        for x in 0..7 {
+            result.push(if row & (1 << x) != 0 { '#' } else { '.' });
+        }
+        result.push_str("|\n");
+    }
+    result.push_str("+-------+\n");
+    return result;
+}
+
 fn simulate(mut jets: Jets, limit: i64) -> usize {
     let mut rocks = Rock::all();
     let mut chamber = vec![127, 0, 0, 0];
@@ -158,17 +190,7 @@ fn simulate(mut jets: Jets, limit: i64) -> usize {
             cache.insert(state, (i, chamber_height(&chamber)));
         }
 
-        let rock = rocks.next();
-        let mut position: Offset = (2, chamber.len() as i32);
-        loop {
-            position = rock.try_push(&chamber, &position, jets.next());
-            if let Some(new_position) = rock.try_fall(&mut chamber, &position) {
-                position = new_position;
-            } else {
-                rock.freeze(&mut chamber, &position);
-                break;
-            }
-        }
+        rocks.next().drop(&mut chamber, &mut jets);
 
         i += 1;
     }
@@ -186,14 +208,16 @@ fn second(jets: Jets) -> usize {
 }
 
 fn main() {
-    let parsed = parse(&as_str());
-    println!("first = {}", first(parsed.clone()));
-    println!("second = {}", second(parsed.clone()));
+    let parsed = measured("parse", || cached("day17", as_str().as_str(), parse));
+    println!("first = {}", measured("first", || first(parsed.clone())));
+    println!("second = {}", measured("second", || second(parsed.clone())));
+    report_total();
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use aoc_2022_rust::aoc::snapshot::assert_snapshot;
 
     const INPUT: &str = ">>><<><>><<<>><>>><<<>>><<<><<<>><>><<>>";
 
@@ -208,4 +232,15 @@ mod tests {
         let input = parse(&INPUT);
         assert_eq!(1514285714288, second(input));
     }
+
+    #[test]
+    fn test_render() {
+        let mut jets = parse(&INPUT);
+        let mut rocks = Rock::all();
+        let mut chamber = vec![127, 0, 0, 0];
+        for _ in 0..10 {
+            rocks.next().drop(&mut chamber, &mut jets);
+        }
+        assert_snapshot("day17_ten_rocks", &render(&chamber));
+    }
 }
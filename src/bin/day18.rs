@@ -1,6 +1,10 @@
 use std::collections::HashSet;
 
-use aoc_2022_rust::aoc::input::lines;
+use aoc_2022_rust::aoc::{
+    cache::cached,
+    input::lines,
+    memory::{measured, report_total},
+};
 
 type Cube = (i32, i32, i32);
 
@@ -131,14 +135,16 @@ fn second(cubes: &Vec<Cube>) -> usize {
 }
 
 fn main() {
-    let parsed = parse(&lines());
-    println!("first = {}", first(&parsed));
-    println!("second = {}", second(&parsed));
+    let parsed = measured("parse", || cached("day18", &lines(), parse));
+    println!("first = {}", measured("first", || first(&parsed)));
+    println!("second = {}", measured("second", || second(&parsed)));
+    report_total();
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     const INPUT: &str = "2,2,2
 1,2,2
@@ -165,4 +171,12 @@ mod tests {
         let input = parse(&INPUT.lines().map(|l| l.to_string()).collect());
         assert_eq!(58, second(&input));
     }
+
+    proptest! {
+        #[test]
+        fn exterior_within_total(cubes in prop::collection::hash_set((0i32..6, 0i32..6, 0i32..6), 1..60)) {
+            let cubes: Vec<Cube> = cubes.into_iter().collect();
+            prop_assert!(second(&cubes) <= first(&cubes));
+        }
+    }
 }
@@ -1,7 +1,14 @@
-use aoc_2022_rust::aoc::input::lines;
+use aoc_2022_rust::aoc::{
+    cache::cached,
+    input::{file_lines, lines},
+    memory::{measured, report_total},
+    repl::{self, Repl},
+};
+use serde::{Deserialize, Serialize};
 
 type Cost = [u16; 4];
 
+#[derive(Deserialize, Serialize)]
 struct Blueprint {
     costs: [Cost; 4],
 }
@@ -152,10 +159,30 @@ fn second(blueprints: &Input) -> u16 {
         .map(|b| optimize_for_geodes(b, 32))
         .product();
 }
+fn repl(input: &Input) -> Repl<'_, Input> {
+    return Repl::new(input)
+        .command("first", "", |input, _| Ok(first(input).to_string()))
+        .command("second", "", |input, _| Ok(second(input).to_string()))
+        .command("geodes", "<blueprint> <minutes>", |blueprints, args| {
+            let id: usize = repl::arg(args, 0, "blueprint")?;
+            let minutes: u16 = repl::arg(args, 1, "minutes")?;
+            let blueprint = blueprints
+                .get(id.wrapping_sub(1))
+                .ok_or(format!("no blueprint {}", id))?;
+            Ok(optimize_for_geodes(blueprint, minutes).to_string())
+        });
+}
+
 fn main() {
-    let parsed = parse(&lines());
-    println!("first = {}", first(&parsed));
-    println!("second = {}", second(&parsed));
+    if let Some(path) = repl::requested() {
+        let parsed = cached("day19", &file_lines(&path), parse);
+        return repl(&parsed).run_stdio();
+    }
+
+    let parsed = measured("parse", || cached("day19", &lines(), parse));
+    println!("first = {}", measured("first", || first(&parsed)));
+    println!("second = {}", measured("second", || second(&parsed)));
+    report_total();
 }
 
 #[cfg(test)]
@@ -170,4 +197,16 @@ Blueprint 2: Each ore robot costs 2 ore. Each clay robot costs 3 ore. Each obsid
         let input = parse(&INPUT.lines().map(|l| l.to_string()).collect());
         assert_eq!(33, first(&input));
     }
+
+    #[test]
+    fn test_repl() {
+        let input = parse(&INPUT.lines().map(|l| l.to_string()).collect());
+        let repl = repl(&input);
+        assert_eq!(Some("9".to_string()), repl.execute("geodes 1 24"));
+        assert_eq!(Some("12".to_string()), repl.execute("geodes 2 24"));
+        assert_eq!(
+            Some("error: no blueprint 3\nusage: geodes <blueprint> <minutes>".to_string()),
+            repl.execute("geodes 3 24")
+        );
+    }
 }
@@ -1,6 +1,10 @@
 use std::collections::VecDeque;
 
-use aoc_2022_rust::aoc::input::lines;
+use aoc_2022_rust::aoc::{
+    cache::cached,
+    input::lines,
+    memory::{measured, report_total},
+};
 
 type Enumerated = (usize, i64);
 
@@ -8,7 +12,7 @@ fn parse(lines: &Vec<String>) -> Vec<i64> {
     return lines.iter().map(|l| l.parse::<i64>().unwrap()).collect();
 }
 
-fn shuffle(values: &Vec<i64>, key: i64, iterations: usize) -> i64 {
+fn mix(values: &Vec<i64>, key: i64, iterations: usize) -> VecDeque<Enumerated> {
     let mut sequence = values
         .iter()
         .map(|v| v * key)
@@ -29,6 +33,12 @@ fn shuffle(values: &Vec<i64>, key: i64, iterations: usize) -> i64 {
             sequence.push_front((index, number));
         }
     }
+
+    return sequence;
+}
+
+fn shuffle(values: &Vec<i64>, key: i64, iterations: usize) -> i64 {
+    let sequence = mix(values, key, iterations);
     let zero_pos = sequence
         .iter()
         .enumerate()
@@ -50,14 +60,16 @@ fn second(values: &Vec<i64>) -> i64 {
 }
 
 fn main() {
-    let parsed = parse(&lines());
-    println!("first = {}", first(&parsed));
-    println!("second = {}", second(&parsed));
+    let parsed = measured("parse", || cached("day20", &lines(), parse));
+    println!("first = {}", measured("first", || first(&parsed)));
+    println!("second = {}", measured("second", || second(&parsed)));
+    report_total();
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     const INPUT: &str = "1
 2
@@ -78,4 +90,15 @@ mod tests {
         let input = parse(&INPUT.lines().map(|l| l.to_string()).collect());
         assert_eq!(1623178306, second(&input));
     }
+
+    proptest! {
+        #[test]
+        fn mixing_keeps_values(values in prop::collection::vec(-20i64..20, 2..40), key in 1i64..1000) {
+            let mut mixed: Vec<i64> = mix(&values, key, 3).iter().map(|(_, v)| *v).collect();
+            let mut expected: Vec<i64> = values.iter().map(|v| v * key).collect();
+            mixed.sort();
+            expected.sort();
+            prop_assert_eq!(expected, mixed);
+        }
+    }
 }
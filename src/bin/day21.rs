@@ -1,9 +1,15 @@
 use eval::Expr;
 use std::collections::{HashMap, HashSet};
 
-use aoc_2022_rust::aoc::input::lines;
-
-#[derive(Debug, Clone)]
+use aoc_2022_rust::aoc::{
+    cache::cached,
+    input::{file_lines, lines},
+    memory::{measured, report_total},
+    repl::{self, Repl},
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 enum Operation {
     Add,
     Substract,
@@ -22,7 +28,7 @@ impl Operation {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 enum Equation {
     Number(i64),
     Operation(String, Operation, String),
@@ -146,10 +152,41 @@ fn second(equations: &HashMap<String, Equation>) -> i64 {
     }
 }
 
+fn repl(input: &HashMap<String, Equation>) -> Repl<'_, HashMap<String, Equation>> {
+    return Repl::new(input)
+        .command("first", "", |input, _| Ok(first(input).to_string()))
+        .command("second", "", |input, _| Ok(second(input).to_string()))
+        .command("expr", "<monkey>", |equations, args| {
+            let monkey: String = repl::arg(args, 0, "monkey")?;
+            if !equations.contains_key(&monkey) {
+                return Err(format!("no monkey {}", monkey));
+            }
+            Ok(print_for(&monkey, equations))
+        })
+        .command("eval", "<monkey>", |equations, args| {
+            let monkey: String = repl::arg(args, 0, "monkey")?;
+            if !equations.contains_key(&monkey) {
+                return Err(format!("no monkey {}", monkey));
+            }
+            let humn = match equations["humn"] {
+                Equation::Number(v) => v,
+                _ => return Err("bad human".to_string()),
+            };
+            let expr = Expr::new(print_for(&monkey, equations));
+            Ok(solve_for_with_eval(expr, humn).to_string())
+        });
+}
+
 fn main() {
-    let parsed = parse(&lines());
-    println!("first = {}", first(&parsed));
-    println!("second = {}", second(&parsed));
+    if let Some(path) = repl::requested() {
+        let parsed = cached("day21", &file_lines(&path), parse);
+        return repl(&parsed).run_stdio();
+    }
+
+    let parsed = measured("parse", || cached("day21", &lines(), parse));
+    println!("first = {}", measured("first", || first(&parsed)));
+    println!("second = {}", measured("second", || second(&parsed)));
+    report_total();
 }
 
 #[cfg(test)]
@@ -183,4 +220,13 @@ hmdt: 32";
         let input = parse(&INPUT.lines().map(|l| l.to_string()).collect());
         assert_eq!(301, second(&input));
     }
+
+    #[test]
+    fn test_repl() {
+        let input = parse(&INPUT.lines().map(|l| l.to_string()).collect());
+        let repl = repl(&input);
+        assert_eq!(Some("150".to_string()), repl.execute("eval sjmn"));
+        assert_eq!(Some("(humn - 3)".to_string()), repl.execute("expr ptdq"));
+        assert_eq!(Some("152".to_string()), repl.execute("eval root"));
+    }
 }
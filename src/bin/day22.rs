@@ -1,8 +1,13 @@
 use std::collections::{HashMap, HashSet, VecDeque};
 
-use aoc_2022_rust::aoc::input::lines;
-
-#[derive(Clone, Debug, PartialEq)]
+use aoc_2022_rust::aoc::{
+    cache::cached,
+    input::lines,
+    memory::{measured, report_total},
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 enum Tile {
     None,
     Open,
@@ -20,14 +25,14 @@ impl Tile {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 enum Instruction {
     Move(usize),
     Left,
     Right,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 struct Input {
     grid: Vec<Vec<Tile>>,
     instructions: Vec<Instruction>,
@@ -182,9 +187,10 @@ fn second(input: &Input) -> usize {
 }
 
 fn main() {
-    let parsed = parse(&lines());
-    println!("first = {}", first(&parsed));
-    println!("second = {}", second(&parsed));
+    let parsed = measured("parse", || cached("day22", &lines(), parse));
+    println!("first = {}", measured("first", || first(&parsed)));
+    println!("second = {}", measured("second", || second(&parsed)));
+    report_total();
 }
 
 #[cfg(test)]
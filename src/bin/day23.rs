@@ -1,6 +1,10 @@
 use std::collections::{HashMap, HashSet, VecDeque};
 
-use aoc_2022_rust::aoc::input::lines;
+use aoc_2022_rust::aoc::{
+    cache::cached,
+    input::lines,
+    memory::{measured, report_total},
+};
 
 type Position = (i32, i32);
 
@@ -140,6 +144,23 @@ fn simulate_rounds(grid: &Grid, rounds: usize) -> (Grid, usize) {
     return (our_grid, rounds);
 }
 
+#[cfg(test)]
+fn render(grid: &Grid) -> String {
+    let min_x = grid.iter().map(|(x, _)| *x).min().unwrap();
+    let max_x = grid.iter().map(|(x, _)| *x).max().unwrap();
+    let min_y = grid.iter().map(|(_, y)| *y).min().unwrap();
+    let max_y = grid.iter().map(|(_, y)| *y).max().unwrap();
+
+    let mut result = String::new();
+    for y in min_y..=max_y {
+        for x in min_x..=max_x {
+            result.push(if grid.contains(&(x, y)) { '#' } else { '.' });
+        }
+        result.push('\n');
+    }
+    return result;
+}
+
 fn first(grid: &Grid) -> i32 {
     let (our_grid, _) = simulate_rounds(&grid, 10);
 
@@ -158,14 +179,16 @@ fn second(grid: &Grid) -> usize {
 }
 
 fn main() {
-    let parsed = parse(&lines());
-    println!("first = {}", first(&parsed));
-    println!("second = {}", second(&parsed));
+    let parsed = measured("parse", || cached("day23", &lines(), parse));
+    println!("first = {}", measured("first", || first(&parsed)));
+    println!("second = {}", measured("second", || second(&parsed)));
+    report_total();
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use aoc_2022_rust::aoc::snapshot::assert_snapshot;
 
     const INPUT: &str = "....#..
 ..###.#
@@ -186,4 +209,11 @@ mod tests {
         let input = parse(&INPUT.lines().map(|l| l.to_string()).collect());
         assert_eq!(20, second(&input));
     }
+
+    #[test]
+    fn test_render() {
+        let input = parse(&INPUT.lines().map(|l| l.to_string()).collect());
+        let (after_ten, _) = simulate_rounds(&input, 10);
+        assert_snapshot("day23_ten_rounds", &render(&after_ten));
+    }
 }
@@ -1,6 +1,11 @@
 use std::collections::{HashSet, VecDeque};
 
-use aoc_2022_rust::aoc::input::lines;
+use aoc_2022_rust::aoc::{
+    cache::cached,
+    input::lines,
+    memory::{measured, report_total},
+};
+use serde::{Deserialize, Serialize};
 
 type Position = (usize, usize);
 type Direction = (i32, i32);
@@ -31,7 +36,7 @@ impl Blizzard {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Deserialize, Serialize)]
 struct Grid {
     walls: Vec<Vec<Tile>>,
     width: usize,
@@ -179,9 +184,10 @@ fn second(grid: &Grid) -> usize {
 }
 
 fn main() {
-    let parsed = parse(&lines());
-    println!("first = {}", first(&parsed));
-    println!("second = {}", second(&parsed));
+    let parsed = measured("parse", || cached("day24", &lines(), parse));
+    println!("first = {}", measured("first", || first(&parsed)));
+    println!("second = {}", measured("second", || second(&parsed)));
+    report_total();
 }
 
 #[cfg(test)]
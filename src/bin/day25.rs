@@ -1,4 +1,7 @@
-use aoc_2022_rust::aoc::input::lines;
+use aoc_2022_rust::aoc::{
+    input::lines,
+    memory::{measured, report_total},
+};
 
 fn from_snafu(number: &String) -> u64 {
     let l = number.len();
@@ -61,14 +64,16 @@ fn second(_: &Vec<String>) -> String {
 }
 
 fn main() {
-    let parsed = lines();
-    println!("first = {}", first(&parsed));
-    println!("second = {}", second(&parsed));
+    let parsed = measured("parse", lines);
+    println!("first = {}", measured("first", || first(&parsed)));
+    println!("second = {}", measured("second", || second(&parsed)));
+    report_total();
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     const INPUT: &str = "1=-0-2
 12111
@@ -89,4 +94,11 @@ mod tests {
         let input = INPUT.lines().map(|l| l.to_string()).collect();
         assert_eq!("2=-1=0", first(&input));
     }
+
+    proptest! {
+        #[test]
+        fn snafu_round_trip(n in 0u64..1_000_000_000_000_000) {
+            prop_assert_eq!(n, from_snafu(&to_snafu(n)));
+        }
+    }
 }
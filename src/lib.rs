@@ -1,6 +1,16 @@
 pub mod aoc {
+    pub mod cache;
+    pub mod interval;
+    pub mod memory;
+    pub mod repl;
+    pub mod snapshot;
+
     pub mod input {
-        use std::io::{self, BufRead};
+        use std::{
+            fs::File,
+            io::{self, BufRead, BufReader},
+            path::Path,
+        };
 
         pub fn as_str() -> String {
             let mut buffer = String::new();
@@ -21,5 +31,10 @@ pub mod aoc {
                 .map(|l| l.unwrap().trim_matches('\n').to_string())
                 .collect();
         }
+
+        pub fn file_lines(path: &Path) -> Vec<String> {
+            let file = File::open(path).expect("cannot open input file");
+            BufReader::new(file).lines().map(|l| l.unwrap()).collect()
+        }
     }
 }